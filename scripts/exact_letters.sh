@@ -0,0 +1,7 @@
+clear
+
+./target/publish/exact_letters.exe \
+    -d ./data/digraphs.json \
+    -l 15 \
+    --frozen-left ser \
+    --results-count 20
@@ -0,0 +1,11 @@
+clear
+
+./target/publish/genetic_groups.exe \
+    -d ./data/digraphs.json \
+    -p 1000 \
+    -c 20 \
+    -g 10000 \
+    -m 4 \
+    --groups 3,3,4,4,4,4,3,3 \
+    --results-count 20 \
+    --repeats-count 500
@@ -0,0 +1,19 @@
+mod report;
+
+use ed_balance::models::CliSettings;
+use report::run;
+use std::process;
+use structopt::StructOpt;
+
+// load digraphs and a split.
+// render the heaviest same-hand pairs.
+// render cross-hand coverage and letters contributions.
+// find the best single swap.
+
+fn main() {
+    let args = CliSettings::from_args();
+    if let Err(e) = run(args) {
+        eprintln!("Analysis failed: {:#?}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,217 @@
+use ed_balance::models::{
+    calculate_score, print_letters, BalancePenalty, CliSettings, Digraphs, DynError, Split,
+};
+use itertools::Itertools;
+
+pub fn run(settings: CliSettings) -> Result<(), DynError> {
+    let sources = settings.digraphs.clone().ok_or("Digraphs are required.")?;
+    let split = settings.split.clone().ok_or("A split is required.")?;
+    let alphabet = split.left.iter().chain(&split.right).copied().collect_vec();
+    let digraphs = Digraphs::load_sources(&sources, &alphabet, settings.strict)?
+        .with_direction(settings.digraphs_direction);
+    let top = settings.results_count as usize;
+    let penalty = settings.balance_penalty.unwrap_or(BalancePenalty::Letters);
+
+    let left_score = digraphs.calculate_score(&split.left);
+    let right_score = digraphs.calculate_score(&split.right);
+    let components = digraphs.calculate_component_scores(&split.left, &split.right);
+    print_letters(
+        &split.left,
+        &split.right,
+        left_score,
+        right_score,
+        &penalty,
+        &components,
+    );
+
+    for (name, letters, score) in [
+        ("left", &split.left, left_score),
+        ("right", &split.right, right_score),
+    ] {
+        println!("\nheaviest {} pairs:", name);
+        for (first, second, value) in get_heaviest_pairs(&digraphs, letters).iter().take(top) {
+            println!("{}{}; {:.3};", first, second, value);
+        }
+
+        println!("\n{} contributions:", name);
+        for (letter, value) in get_contributions(&digraphs, letters) {
+            let share = if score == 0. { 0. } else { value / score };
+            println!("{}; {:.3}; {:.1}%;", letter, value, share * 100.);
+        }
+    }
+
+    println!(
+        "\ncross-hand coverage: {:.1}%",
+        get_cross_coverage(&digraphs, &split)
+    );
+
+    let frozen = settings
+        .frozen_left
+        .chars()
+        .chain(settings.frozen_right.chars())
+        .collect_vec();
+    match find_best_swap(&digraphs, &split, &frozen, &penalty) {
+        Some((left, right, score)) => println!(
+            "best swap: {} <-> {}; {:.3} -> {:.3};",
+            left,
+            right,
+            calculate_score(left_score, right_score, &penalty),
+            score
+        ),
+        None => println!("best swap: no single swap improves the score."),
+    }
+
+    Ok(())
+}
+
+/// Same-hand pairs from the heaviest to the lightest, empty pairs are skipped.
+fn get_heaviest_pairs(digraphs: &Digraphs, letters: &[char]) -> Vec<(char, char, f64)> {
+    letters
+        .iter()
+        .tuple_combinations()
+        .map(|(&first, &second)| (first, second, digraphs.get_pair_score(first, second)))
+        .filter(|(_, _, value)| *value != 0.)
+        .sorted_by(|(_, _, a), (_, _, b)| b.partial_cmp(a).unwrap())
+        .collect()
+}
+
+/// Each pair is shared by two letters, so halves of pairs are counted
+/// and contributions sum up to the group score.
+fn get_contributions(digraphs: &Digraphs, letters: &[char]) -> Vec<(char, f64)> {
+    letters
+        .iter()
+        .map(|&letter| {
+            let value: f64 = letters
+                .iter()
+                .filter(|&&x| x != letter)
+                .map(|&x| digraphs.get_pair_score(letter, x) / 2.)
+                .sum();
+            (letter, value)
+        })
+        .sorted_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap())
+        .collect()
+}
+
+/// Percent of all pairs of the split letters that are typed by different hands.
+fn get_cross_coverage(digraphs: &Digraphs, split: &Split) -> f64 {
+    let cross: f64 = split
+        .left
+        .iter()
+        .cartesian_product(split.right.iter())
+        .map(|(&left, &right)| digraphs.get_pair_score(left, right))
+        .sum();
+    let total =
+        cross + digraphs.calculate_score(&split.left) + digraphs.calculate_score(&split.right);
+
+    if total == 0. {
+        return 0.;
+    }
+
+    cross / total * 100.
+}
+
+/// Higher score is better, the same as in `genetic_letters`.
+fn find_best_swap(
+    digraphs: &Digraphs,
+    split: &Split,
+    frozen: &[char],
+    penalty: &BalancePenalty,
+) -> Option<(char, char, f64)> {
+    let current = calculate_score(
+        digraphs.calculate_score(&split.left),
+        digraphs.calculate_score(&split.right),
+        penalty,
+    );
+
+    split
+        .left
+        .iter()
+        .cartesian_product(split.right.iter())
+        .filter(|(left, right)| !frozen.contains(left) && !frozen.contains(right))
+        .map(|(&left, &right)| {
+            let swap = |letters: &[char]| {
+                letters
+                    .iter()
+                    .map(|&x| match x {
+                        x if x == left => right,
+                        x if x == right => left,
+                        x => x,
+                    })
+                    .collect_vec()
+            };
+            let score = calculate_score(
+                digraphs.calculate_score(&swap(&split.left)),
+                digraphs.calculate_score(&swap(&split.right)),
+                penalty,
+            );
+            (left, right, score)
+        })
+        .filter(|(_, _, score)| *score > current)
+        .max_by(|(_, _, a), (_, _, b)| a.partial_cmp(b).unwrap())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn default_digraphs() -> Digraphs {
+        let json = json!({
+            "ab": 3.0,
+            "ba": 1.0,
+            "ac": 1.0,
+            "cd": 2.0,
+            "bd": 5.0,
+        });
+
+        Digraphs::new(json.as_object().unwrap())
+    }
+
+    #[test]
+    fn should_sort_heaviest_pairs() {
+        let actual = get_heaviest_pairs(&default_digraphs(), &['a', 'b', 'c']);
+
+        assert_eq!(actual, vec![('a', 'b', 4.), ('a', 'c', 1.)]);
+    }
+
+    #[test]
+    fn contributions_should_sum_to_score() {
+        let digraphs = default_digraphs();
+        let letters = ['a', 'b', 'c'];
+
+        let actual = get_contributions(&digraphs, &letters);
+
+        assert_eq!(actual, vec![('a', 2.5), ('b', 2.), ('c', 0.5)]);
+        assert_eq!(
+            actual.iter().map(|(_, x)| x).sum::<f64>(),
+            digraphs.calculate_score(&letters)
+        );
+    }
+
+    #[test]
+    fn should_calculate_cross_coverage() {
+        let split: Split = "ab cd".parse().unwrap();
+
+        let actual = get_cross_coverage(&default_digraphs(), &split);
+
+        assert_eq!(actual, 6. / 12. * 100.);
+    }
+
+    #[test]
+    fn should_find_best_swap() {
+        let digraphs = default_digraphs();
+        let split: Split = "ac bd".parse().unwrap();
+
+        // "a <-> d" and "c <-> b" give the same split, so "c" is frozen.
+        let actual = find_best_swap(&digraphs, &split, &['c'], &BalancePenalty::Letters);
+
+        assert_eq!(
+            actual.map(|(left, right, _)| (left, right)),
+            Some(('a', 'd'))
+        );
+        assert_eq!(
+            find_best_swap(&digraphs, &split, &['a', 'c'], &BalancePenalty::Letters),
+            None
+        );
+    }
+}
@@ -0,0 +1,16 @@
+mod table;
+
+use ed_balance::models::CliSettings;
+use std::process;
+use structopt::StructOpt;
+use table::run;
+
+// print how the selected balance penalty treats different left/right ratios.
+
+fn main() {
+    let args = CliSettings::from_args();
+    if let Err(e) = run(args) {
+        eprintln!("Rendering failed: {:#?}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,40 @@
+use ed_balance::models::{BalancePenalty, CliSettings, DynError};
+
+const MIN_RATIO: f64 = 0.5;
+const MAX_RATIO: f64 = 2.;
+const STEP: f64 = 0.05;
+
+pub fn run(settings: CliSettings) -> Result<(), DynError> {
+    let penalties = match settings.balance_penalty {
+        Some(penalty) => vec![penalty],
+        None => vec![BalancePenalty::Letters, BalancePenalty::Keyboard],
+    };
+
+    for penalty in penalties {
+        println!("{}", penalty);
+        println!("left/right; penalty; score factor; effort factor;");
+
+        for (left, right) in get_ratios() {
+            println!(
+                "{:.2}; {:.3}; {:.3}; {:.3};",
+                left / right,
+                penalty.penalty(left, right),
+                penalty.score_factor(left, right),
+                penalty.effort_factor(left, right)
+            );
+        }
+
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Pairs of left and right values from `MIN_RATIO` to `MAX_RATIO`.
+fn get_ratios() -> Vec<(f64, f64)> {
+    let steps = ((MAX_RATIO - MIN_RATIO) / STEP).round() as usize;
+
+    (0..=steps)
+        .map(|i| (MIN_RATIO + STEP * i as f64, 1.))
+        .collect()
+}
@@ -1,102 +1,333 @@
-use ed_balance::models::{calculate_score, print_letters, CliSettings, Digraphs, DynError};
-use std::collections::VecDeque;
-
-// find a pair to move to the right group that will give biggest result
-// iterate letters to find which will give biggest sum of both groups
-// calculate value of the left group without current letter
-// calculate right groups with new letter
-// when move joined letters move them all (zxcv, th?, er?)
-// select maximum combination
-// print maximized groups
-// continue till left group has 11 letters (because rest 4 can be used for punctuation keys)
+use ed_balance::models::{
+    calculate_score, format_records, BalancePenalty, CliSettings, Digraphs, DynError,
+    LettersRecord, Split,
+};
+use itertools::Itertools;
+use std::collections::HashSet;
+
+// start with all letters on the left except one
+// move letters one by one to the right group, higher score is better like in other letters solvers
+// keep `beam-width` best splits on each step instead of only one
+// report splits with `left-count` down to `min-left-count` letters on the left
+// improve each reported split by swapping letters while it gets better
+// the same split found from different starting letters is reported once
+
+struct Constraints {
+    frozen_left: Vec<char>,
+    frozen_right: Vec<char>,
+    min_left_count: usize,
+    max_left_count: usize,
+    beam_width: usize,
+}
+
+impl Constraints {
+    fn is_frozen(&self, letter: char) -> bool {
+        self.frozen_left.contains(&letter) || self.frozen_right.contains(&letter)
+    }
+}
 
 pub fn run(settings: CliSettings) -> Result<(), DynError> {
-    let path = settings.digraphs.clone().unwrap();
-    let digraphs = Digraphs::load(&path)?;
-    for letter in 'a'..='z' {
-        start_with(&letter, &digraphs, &settings.frozen_left)
+    let sources = settings.digraphs.clone().ok_or("Digraphs are required.")?;
+    let alphabet = ('a'..='z').collect_vec();
+    let digraphs = Digraphs::load_sources(&sources, &alphabet, settings.strict)?
+        .with_direction(settings.digraphs_direction);
+    let penalty = settings.balance_penalty.unwrap_or(BalancePenalty::Letters);
+    let constraints = Constraints {
+        frozen_left: settings.frozen_left.chars().collect(),
+        frozen_right: settings.frozen_right.chars().collect(),
+        min_left_count: settings.min_left_count as usize,
+        max_left_count: settings.left_count as usize,
+        beam_width: settings.beam_width.max(1) as usize,
+    };
+
+    if constraints.min_left_count > constraints.max_left_count {
+        return Err("The min left count must not be bigger than the left count.".into());
     }
 
+    if let Some(letter) = constraints
+        .frozen_left
+        .iter()
+        .find(|x| constraints.frozen_right.contains(x))
+    {
+        return Err(format!("'{}' is frozen on both hands.", letter).into());
+    }
+
+    let records = solve(&digraphs, &penalty, &constraints)
+        .into_iter()
+        .map(|split| {
+            let left_score = digraphs.calculate_score(&split.left);
+            let right_score = digraphs.calculate_score(&split.right);
+            let components = digraphs.calculate_component_scores(&split.left, &split.right);
+            LettersRecord::new(
+                &split.left,
+                &split.right,
+                left_score,
+                right_score,
+                &penalty,
+                &components,
+            )
+        })
+        .collect_vec();
+    println!("{}", format_records(&records, settings.output_format)?);
+
     Ok(())
 }
 
-fn start_with(letter: &char, digraphs: &Digraphs, frozen: &str) {
-    let mut right_letters: VecDeque<char> = VecDeque::with_capacity(15);
-    right_letters.push_back(*letter);
+/// Unique splits of all starting letters,
+/// from the biggest left group to the smallest and then from the best score.
+fn solve(digraphs: &Digraphs, penalty: &BalancePenalty, constraints: &Constraints) -> Vec<Split> {
+    ('a'..='z')
+        .filter(|x| !constraints.frozen_left.contains(x))
+        .flat_map(|letter| start_with(letter, digraphs, penalty, constraints))
+        .unique_by(|split| split.left.clone())
+        .map(|split| improve(split, digraphs, penalty, constraints))
+        .unique_by(|split| split.left.clone())
+        .map(|split| (evaluate(&split, digraphs, penalty), split))
+        .sorted_by(|(a_score, a), (b_score, b)| {
+            b.left
+                .len()
+                .cmp(&a.left.len())
+                .then_with(|| b_score.total_cmp(a_score))
+                .then_with(|| a.left.cmp(&b.left))
+        })
+        .map(|(_, split)| split)
+        .collect()
+}
+
+fn start_with(
+    letter: char,
+    digraphs: &Digraphs,
+    penalty: &BalancePenalty,
+    constraints: &Constraints,
+) -> Vec<Split> {
+    let right = constraints
+        .frozen_right
+        .iter()
+        .copied()
+        .chain(Some(letter))
+        .unique()
+        .sorted()
+        .collect_vec();
+    let left = ('a'..='z').filter(|x| !right.contains(x)).collect_vec();
 
-    let mut left_letters: VecDeque<char> =
-        ('a'..='z').filter(|x| !right_letters.contains(x)).collect();
+    let mut beam = vec![Split { left, right }];
+    let mut results = Vec::new();
 
     loop {
-        let (letter, index, left_score, right_score) =
-            get_letter_to_move(&digraphs, &mut left_letters, &mut right_letters, &frozen);
-
-        let mut split = left_letters.split_off(index);
-        split.pop_front();
-        left_letters.append(&mut split);
-        right_letters.push_back(letter);
-
-        if left_letters.len() <= 15 {
-            let left: Vec<_> = to_vec(&left_letters);
-            let right: Vec<_> = to_vec(&right_letters);
-            print_letters(&left, &right, left_score, right_score);
+        let left_count = beam[0].left.len();
+
+        if left_count <= constraints.max_left_count {
+            results.extend(beam.iter().cloned());
+        }
+        if left_count <= constraints.min_left_count {
+            break;
         }
-        if left_letters.len() <= 11 {
+
+        let next = get_next_beam(&beam, digraphs, penalty, constraints);
+        if next.is_empty() {
             break;
         }
+        beam = next;
     }
+
+    results
 }
 
-fn get_letter_to_move(
+/// All splits with one more letter moved to the right, only `beam_width` best are kept.
+fn get_next_beam(
+    beam: &[Split],
     digraphs: &Digraphs,
-    left_letters: &mut VecDeque<char>,
-    right_letters: &mut VecDeque<char>,
-    frozen: &str,
-) -> (char, usize, f64, f64) {
-    let mut left_result = 0.;
-    let mut right_result = 0.;
-    let mut result = None;
-    let mut i = 0;
-    let mut index = 0;
-    let mut min_total = 100000.;
-
-    while i < left_letters.len() {
-        if let Some(letter) = left_letters.pop_front() {
-            if !frozen.contains(letter) {
-                let left: Vec<_> = left_letters.iter().map(|x| *x).collect();
-                let left_score = digraphs.calculate_score(&left);
-
-                right_letters.push_back(letter);
-                let right: Vec<_> = right_letters.iter().map(|x| *x).collect();
-                let right_score = digraphs.calculate_score(&right);
-
-                let total_score = calculate_score(left_score, right_score);
-                if total_score < min_total {
-                    left_result = left_score;
-                    right_result = right_score;
-                    result = Some(letter);
-                    index = i;
-                    min_total = total_score;
-                }
-
-                right_letters.pop_back();
-            }
+    penalty: &BalancePenalty,
+    constraints: &Constraints,
+) -> Vec<Split> {
+    let mut seen = HashSet::new();
+
+    beam.iter()
+        .flat_map(|split| {
+            split
+                .left
+                .iter()
+                .filter(|&&x| !constraints.is_frozen(x))
+                .map(move |&letter| Split {
+                    left: split
+                        .left
+                        .iter()
+                        .copied()
+                        .filter(|&x| x != letter)
+                        .collect(),
+                    right: split
+                        .right
+                        .iter()
+                        .copied()
+                        .chain(Some(letter))
+                        .sorted()
+                        .collect(),
+                })
+        })
+        .filter(|split| seen.insert(split.left.clone()))
+        .map(|split| (evaluate(&split, digraphs, penalty), split))
+        .filter(|(score, _)| !score.is_nan())
+        .sorted_by(|(a, _), (b, _)| b.total_cmp(a))
+        .take(constraints.beam_width)
+        .map(|(_, split)| split)
+        .collect()
+}
+
+/// Swaps letters between groups while the score gets higher.
+fn improve(
+    mut split: Split,
+    digraphs: &Digraphs,
+    penalty: &BalancePenalty,
+    constraints: &Constraints,
+) -> Split {
+    let mut score = evaluate(&split, digraphs, penalty);
+
+    loop {
+        let best = split
+            .left
+            .iter()
+            .cartesian_product(split.right.iter())
+            .filter(|(&left, &right)| !constraints.is_frozen(left) && !constraints.is_frozen(right))
+            .map(|(&left, &right)| {
+                let swap = |letters: &[char], from: char, to: char| {
+                    letters
+                        .iter()
+                        .map(|&x| if x == from { to } else { x })
+                        .sorted()
+                        .collect_vec()
+                };
+                let swapped = Split {
+                    left: swap(&split.left, left, right),
+                    right: swap(&split.right, right, left),
+                };
+                (evaluate(&swapped, digraphs, penalty), swapped)
+            })
+            .filter(|(x, _)| *x > score)
+            .max_by(|(a, _), (b, _)| a.total_cmp(b));
 
-            left_letters.push_back(letter);
-        } else {
-            panic!("should not get here");
+        match best {
+            Some((best_score, best_split)) => {
+                score = best_score;
+                split = best_split;
+            }
+            None => return split,
         }
-        i += 1;
     }
+}
 
-    (
-        result.expect("failed to find a letter to move"),
-        index,
-        left_result,
-        right_result,
+fn evaluate(split: &Split, digraphs: &Digraphs, penalty: &BalancePenalty) -> f64 {
+    calculate_score(
+        digraphs.calculate_score(&split.left),
+        digraphs.calculate_score(&split.right),
+        penalty,
     )
 }
 
-fn to_vec(list: &VecDeque<char>) -> Vec<char> {
-    list.iter().map(|x| *x).collect()
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn default_digraphs() -> Digraphs {
+        let json = json!({
+            "ab": 4.0,
+            "cd": 3.0,
+            "ef": 2.0,
+            "ac": 1.0,
+            "bd": 1.0,
+            "gh": 2.0,
+        });
+
+        Digraphs::new(json.as_object().unwrap())
+    }
+
+    fn default_constraints() -> Constraints {
+        Constraints {
+            frozen_left: Vec::new(),
+            frozen_right: Vec::new(),
+            min_left_count: 22,
+            max_left_count: 23,
+            beam_width: 2,
+        }
+    }
+
+    #[test]
+    fn should_report_configured_sizes_once() {
+        let actual = solve(
+            &default_digraphs(),
+            &BalancePenalty::Letters,
+            &default_constraints(),
+        );
+
+        assert!(!actual.is_empty());
+        assert!(actual
+            .iter()
+            .all(|x| (22..=23).contains(&x.left.len()) && x.left.len() + x.right.len() == 26));
+        assert_eq!(
+            actual.iter().map(|x| &x.left).unique().count(),
+            actual.len()
+        );
+        for window in actual.windows(2) {
+            assert!(window[0].left.len() >= window[1].left.len());
+        }
+    }
+
+    #[test]
+    fn should_respect_frozen_letters() {
+        let constraints = Constraints {
+            frozen_left: vec!['a', 'c'],
+            frozen_right: vec!['b'],
+            ..default_constraints()
+        };
+
+        let actual = solve(&default_digraphs(), &BalancePenalty::Letters, &constraints);
+
+        assert!(!actual.is_empty());
+        for split in actual {
+            assert!(split.left.contains(&'a') && split.left.contains(&'c'));
+            assert!(split.right.contains(&'b'));
+        }
+    }
+
+    #[test]
+    fn should_find_best_split_of_exhaustive_search() {
+        let digraphs = default_digraphs();
+        let constraints = Constraints {
+            min_left_count: 22,
+            max_left_count: 22,
+            beam_width: 10,
+            ..default_constraints()
+        };
+        let expected = ('a'..='z')
+            .combinations(4)
+            .map(|right| Split {
+                left: ('a'..='z').filter(|x| !right.contains(x)).collect(),
+                right,
+            })
+            .map(|x| evaluate(&x, &digraphs, &BalancePenalty::Letters))
+            .fold(f64::MIN, f64::max);
+
+        let actual = solve(&digraphs, &BalancePenalty::Letters, &constraints);
+
+        assert_eq!(
+            evaluate(&actual[0], &digraphs, &BalancePenalty::Letters),
+            expected
+        );
+    }
+
+    #[test]
+    fn should_improve_by_swaps() {
+        let digraphs = default_digraphs();
+        let split: Split = "acegijklmnopqrstuvwxyz bdfh".parse().unwrap();
+        let score = evaluate(&split, &digraphs, &BalancePenalty::Letters);
+
+        let actual = improve(
+            split,
+            &digraphs,
+            &BalancePenalty::Letters,
+            &default_constraints(),
+        );
+
+        assert!(evaluate(&actual, &digraphs, &BalancePenalty::Letters) > score);
+    }
 }
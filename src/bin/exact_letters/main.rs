@@ -0,0 +1,19 @@
+mod solver;
+
+use ed_balance::models::CliSettings;
+use solver::run;
+use std::process;
+use structopt::StructOpt;
+
+// enumerate all left groups of the given size that contain the frozen left letters.
+// score each split with precalculated pair scores.
+// keep the best results of each branch and merge them.
+// print the proven best splits.
+
+fn main() {
+    let args = CliSettings::from_args();
+    if let Err(e) = run(args) {
+        eprintln!("Calculations failed: {:#?}", e);
+        process::exit(1);
+    }
+}
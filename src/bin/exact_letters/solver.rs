@@ -0,0 +1,321 @@
+use ed_balance::models::{
+    calculate_score, format_records, BalancePenalty, CliSettings, Digraphs, DynError, LettersRecord,
+};
+use itertools::Itertools;
+use rayon::prelude::*;
+use std::cmp::Ordering;
+
+/// A group of letters as bits of their indexes in the alphabet.
+pub type Mask = u32;
+
+pub fn run(settings: CliSettings) -> Result<(), DynError> {
+    let sources = settings.digraphs.clone().ok_or("Digraphs are required.")?;
+    let alphabet = ('a'..='z').collect_vec();
+    let digraphs = Digraphs::load_sources(&sources, &alphabet, settings.strict)?
+        .with_direction(settings.digraphs_direction);
+    let frozen_left = settings.frozen_left.chars().collect_vec();
+    let frozen_right = settings.frozen_right.chars().collect_vec();
+    let penalty = settings.balance_penalty.unwrap_or(BalancePenalty::Letters);
+
+    let results = solve(
+        &digraphs,
+        &penalty,
+        &alphabet,
+        &frozen_left,
+        &frozen_right,
+        settings.left_count as usize,
+        settings.results_count as usize,
+    )?;
+
+    let records = results
+        .into_iter()
+        .map(|(left, _)| {
+            let right = to_letters(&alphabet, !left);
+            let left = to_letters(&alphabet, left);
+            let left_score = digraphs.calculate_score(&left);
+            let right_score = digraphs.calculate_score(&right);
+            let components = digraphs.calculate_component_scores(&left, &right);
+            LettersRecord::new(
+                &left,
+                &right,
+                left_score,
+                right_score,
+                &penalty,
+                &components,
+            )
+        })
+        .collect_vec();
+    println!("{}", format_records(&records, settings.output_format)?);
+
+    Ok(())
+}
+
+/// Checks every left group of `left_count` letters that respects frozen letters
+/// and returns the best splits with their `calculate_score`.\
+/// Higher score is better, the same as in `genetic_letters`.
+pub fn solve(
+    digraphs: &Digraphs,
+    penalty: &BalancePenalty,
+    alphabet: &[char],
+    frozen_left: &[char],
+    frozen_right: &[char],
+    left_count: usize,
+    results_count: usize,
+) -> Result<Vec<(Mask, f64)>, String> {
+    if alphabet.len() > Mask::BITS as usize {
+        return Err(format!("At most {} letters are supported.", Mask::BITS));
+    }
+
+    let to_mask = |letters: &[char]| {
+        letters.iter().try_fold(0 as Mask, |mask, letter| {
+            match alphabet.iter().position(|x| x == letter) {
+                Some(index) => Ok(mask | 1 << index),
+                None => Err(format!("'{}' is not in the alphabet.", letter)),
+            }
+        })
+    };
+    let frozen_left = to_mask(frozen_left)?;
+    let frozen_right = to_mask(frozen_right)?;
+
+    if frozen_left & frozen_right != 0 {
+        return Err("Frozen left and right letters must not intersect.".to_string());
+    }
+
+    let free = (0..alphabet.len())
+        .filter(|&i| (frozen_left | frozen_right) & 1 << i == 0)
+        .collect_vec();
+    let count = left_count
+        .checked_sub(frozen_left.count_ones() as usize)
+        .filter(|&x| x <= free.len())
+        .ok_or_else(|| format!("{} letters cannot be on the left hand.", left_count))?;
+
+    let scores = alphabet
+        .iter()
+        .map(|&a| {
+            alphabet
+                .iter()
+                .map(|&b| {
+                    if a == b {
+                        0.
+                    } else {
+                        digraphs.get_pair_score(a, b)
+                    }
+                })
+                .collect_vec()
+        })
+        .collect_vec();
+    let full = to_mask(alphabet)?;
+    let evaluate = |left: Mask| {
+        let score = calculate_score(
+            get_group_score(&scores, left),
+            get_group_score(&scores, full & !left),
+            penalty,
+        );
+        (left, score)
+    };
+
+    if count == 0 {
+        let mut top = Vec::new();
+        push_top(&mut top, evaluate(frozen_left), results_count);
+        return Ok(top);
+    }
+
+    // branches are split by the first free letter of the left group.
+    let top = (0..=free.len() - count)
+        .into_par_iter()
+        .map(|first| {
+            let head = frozen_left | 1 << free[first];
+            let mut top = Vec::with_capacity(results_count + 1);
+
+            for rest in free[first + 1..].iter().combinations(count - 1) {
+                let left = rest.into_iter().fold(head, |mask, &i| mask | 1 << i);
+                push_top(&mut top, evaluate(left), results_count);
+            }
+
+            top
+        })
+        .reduce(Vec::new, |mut top, other| {
+            for item in other {
+                push_top(&mut top, item, results_count);
+            }
+            top
+        });
+
+    Ok(top)
+}
+
+fn get_group_score(scores: &[Vec<f64>], mask: Mask) -> f64 {
+    let mut score = 0.;
+
+    for i in (0..scores.len()).filter(|&i| mask & 1 << i != 0) {
+        for j in (i + 1..scores.len()).filter(|&j| mask & 1 << j != 0) {
+            score += scores[i][j];
+        }
+    }
+
+    score
+}
+
+/// Keeps `top` sorted from the best, ties are ordered by masks to have stable results.
+fn push_top(top: &mut Vec<(Mask, f64)>, item: (Mask, f64), results_count: usize) {
+    // empty groups give NaN.
+    if item.1.is_nan() {
+        return;
+    }
+
+    let cmp = |a: &(Mask, f64), b: &(Mask, f64)| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.0.cmp(&b.0))
+    };
+    let index = top
+        .binary_search_by(|x| cmp(x, &item))
+        .unwrap_or_else(|x| x);
+
+    if index < results_count {
+        top.insert(index, item);
+        top.truncate(results_count);
+    }
+}
+
+fn to_letters(alphabet: &[char], mask: Mask) -> Vec<char> {
+    alphabet
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| mask & 1 << i != 0)
+        .map(|(_, &x)| x)
+        .collect()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn default_digraphs() -> Digraphs {
+        let json = json!({
+            "ab": 4.0,
+            "cd": 3.0,
+            "ef": 2.0,
+            "ac": 1.0,
+            "bd": 1.0,
+        });
+
+        Digraphs::new(json.as_object().unwrap())
+    }
+
+    #[test]
+    fn should_find_best_splits() {
+        let digraphs = default_digraphs();
+        let alphabet = "abcdef".chars().collect_vec();
+
+        let actual = solve(
+            &digraphs,
+            &BalancePenalty::Letters,
+            &alphabet,
+            &[],
+            &[],
+            3,
+            100,
+        )
+        .unwrap();
+
+        for window in actual.windows(2) {
+            assert!(window[0].1 >= window[1].1);
+        }
+
+        let (best, score) = actual[0];
+        let left = to_letters(&alphabet, best);
+        let right = to_letters(&alphabet, !best & 0b111111);
+        let expected = calculate_score(
+            digraphs.calculate_score(&left),
+            digraphs.calculate_score(&right),
+            &BalancePenalty::Letters,
+        );
+        assert_eq!(score, expected);
+
+        let brute_force = (0..alphabet.len())
+            .combinations(3)
+            .map(|x| {
+                let left = x.iter().map(|&i| alphabet[i]).collect_vec();
+                let right = alphabet
+                    .iter()
+                    .filter(|c| !left.contains(c))
+                    .cloned()
+                    .collect_vec();
+                calculate_score(
+                    digraphs.calculate_score(&left),
+                    digraphs.calculate_score(&right),
+                    &BalancePenalty::Letters,
+                )
+            })
+            // splits where both groups score 0 are skipped.
+            .filter(|x| !x.is_nan())
+            .collect_vec();
+        assert_eq!(actual.len(), brute_force.len());
+        assert_eq!(score, brute_force.into_iter().fold(f64::MIN, f64::max));
+    }
+
+    #[test]
+    fn should_respect_frozen_letters() {
+        let digraphs = default_digraphs();
+        let alphabet = "abcdef".chars().collect_vec();
+
+        let actual = solve(
+            &digraphs,
+            &BalancePenalty::Letters,
+            &alphabet,
+            &['a'],
+            &['b', 'c'],
+            3,
+            100,
+        )
+        .unwrap();
+
+        // "ade" and "adf" leave both groups without digraphs.
+        assert_eq!(actual.len(), 1);
+        for (mask, _) in actual {
+            let left = to_letters(&alphabet, mask);
+            assert!(left.contains(&'a'));
+            assert!(!left.contains(&'b'));
+            assert!(!left.contains(&'c'));
+        }
+    }
+
+    #[test]
+    fn should_validate_frozen_letters() {
+        let digraphs = default_digraphs();
+        let alphabet = "abcdef".chars().collect_vec();
+
+        assert!(solve(
+            &digraphs,
+            &BalancePenalty::Letters,
+            &alphabet,
+            &['a'],
+            &['a'],
+            3,
+            10
+        )
+        .is_err());
+        assert!(solve(
+            &digraphs,
+            &BalancePenalty::Letters,
+            &alphabet,
+            &['a', 'b'],
+            &[],
+            1,
+            10
+        )
+        .is_err());
+        assert!(solve(
+            &digraphs,
+            &BalancePenalty::Letters,
+            &alphabet,
+            &['x'],
+            &[],
+            3,
+            10
+        )
+        .is_err());
+    }
+}
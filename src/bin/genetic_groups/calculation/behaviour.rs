@@ -0,0 +1,252 @@
+use super::{Groups, GroupsPointer, Mutation};
+use ed_balance::{
+    get_version,
+    models::{CliSettings, Digraphs},
+    Context, IBehaviour,
+};
+use itertools::Itertools;
+use rand::{prelude::SliceRandom, thread_rng};
+use std::cmp::Ordering;
+
+pub struct Behaviour {
+    pub context: Context,
+    pub digraphs: Digraphs,
+    pub letters: Vec<char>,
+
+    /// Maximum number of letters in each group.
+    pub capacities: Vec<usize>,
+}
+
+impl Behaviour {
+    fn get_free_groups(&self, groups: &[Vec<char>]) -> Vec<usize> {
+        (0..groups.len())
+            .filter(|&x| groups[x].len() < self.capacities[x])
+            .collect()
+    }
+}
+
+impl IBehaviour<Mutation, Groups> for Behaviour {
+    fn new(settings: &CliSettings) -> Self {
+        let sources = settings.digraphs.clone().unwrap();
+        let letters = ('a'..='z').collect_vec();
+        let digraphs = Digraphs::load_sources(&sources, &letters, settings.strict)
+            .unwrap()
+            .with_direction(settings.digraphs_direction);
+
+        if settings.groups.total() < letters.len() {
+            panic!(
+                "Groups have {} places for {} letters.",
+                settings.groups.total(),
+                letters.len()
+            )
+        }
+
+        Behaviour {
+            context: Context::new(settings),
+            digraphs,
+            letters,
+            capacities: settings.groups.0.clone(),
+        }
+    }
+
+    fn generate(&self) -> GroupsPointer {
+        let mut rng = thread_rng();
+        let mut groups = vec![Vec::new(); self.capacities.len()];
+
+        for &letter in self.letters.iter() {
+            let group = *self
+                .get_free_groups(&groups)
+                .choose(&mut rng)
+                .expect("Groups are full.");
+            groups[group].push(letter);
+        }
+
+        let version = get_version();
+
+        Groups::new(
+            version.clone(),
+            &groups,
+            Vec::new(),
+            version, // versions match to be able cross children with parents
+            groups.clone(),
+            &self.digraphs,
+        )
+    }
+
+    fn calculate_score(&self, individual: &Groups) -> f64 {
+        individual.score
+    }
+
+    fn cross(&self, individual: &Groups, partner: &Groups) -> GroupsPointer {
+        let mut groups = individual.parent.clone();
+        let mut mutations: Vec<_> = individual
+            .mutations
+            .iter()
+            .chain(partner.mutations.iter())
+            .unique()
+            .copied()
+            .collect();
+
+        mutations.shuffle(&mut thread_rng());
+
+        // mutations of different individuals can conflict or overfill groups,
+        // such ones are skipped.
+        let mut applied = Vec::with_capacity(self.context.mutations_count);
+        for mutation in mutations {
+            if applied.len() == self.context.mutations_count {
+                break;
+            }
+
+            if mutation.apply(&mut groups, &self.capacities) {
+                applied.push(mutation);
+            }
+        }
+
+        Groups::new(
+            get_version(),
+            &groups,
+            applied,
+            individual.parent_version.clone(),
+            individual.parent.clone(),
+            &self.digraphs,
+        )
+    }
+
+    fn mutate(&self, individual: &Groups) -> GroupsPointer {
+        let mut rng = thread_rng();
+        let mut groups = individual.groups.clone();
+        let group_of =
+            |groups: &[Vec<char>], letter: char| groups.iter().position(|x| x.contains(&letter));
+
+        // each letter changes its group once, so mutations do not revert each other.
+        let mut free = groups.concat();
+        free.shuffle(&mut rng);
+
+        let mut mutations = Vec::with_capacity(self.context.mutations_count);
+
+        while mutations.len() < self.context.mutations_count {
+            let letter = match free.pop() {
+                Some(letter) => letter,
+                None => break,
+            };
+            let group = group_of(&groups, letter);
+
+            let mut candidates = Vec::with_capacity(2);
+            if let Some(&second) = free.iter().find(|&&x| group_of(&groups, x) != group) {
+                candidates.push(Mutation::Swap {
+                    first: letter,
+                    second,
+                });
+            }
+            if let Some(&target) = self
+                .get_free_groups(&groups)
+                .iter()
+                .filter(|&&x| Some(x) != group)
+                .collect_vec()
+                .choose(&mut rng)
+            {
+                candidates.push(Mutation::Move {
+                    letter,
+                    group: *target,
+                });
+            }
+
+            if let Some(&mutation) = candidates.choose(&mut rng) {
+                mutation.apply(&mut groups, &self.capacities);
+                free.retain(|&x| !mutation.contains(x));
+                mutations.push(mutation);
+            }
+        }
+
+        Groups::new(
+            get_version(),
+            &groups,
+            mutations,
+            individual.version.clone(),
+            individual.groups.clone(),
+            &self.digraphs,
+        )
+    }
+
+    fn get_context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Lower penalty is better.
+    fn score_cmp(&self, a: &Groups, b: &Groups) -> Ordering {
+        a.score
+            .partial_cmp(&b.score)
+            .unwrap()
+            .then_with(|| a.groups.cmp(&b.groups))
+    }
+
+    fn load(&self) -> std::io::Result<Vec<Box<Groups>>> {
+        Ok(Vec::new())
+    }
+
+    fn save(&self, _: &[Box<Groups>]) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn default_behaviour() -> Behaviour {
+        let json = json!({ "ab": 2.0, "cd": 4.0, "ac": 8.0 });
+
+        Behaviour {
+            context: Context {
+                mutations_count: 3,
+                population_size: 10,
+                children_count: 10,
+                generations_count: 10,
+                results_count: 10,
+                left_count: 15,
+                repeats_count: 10,
+            },
+            digraphs: Digraphs::new(json.as_object().unwrap()),
+            letters: ('a'..='z').collect(),
+            capacities: vec![3, 3, 4, 4, 4, 4, 3, 3],
+        }
+    }
+
+    fn assert_groups(behaviour: &Behaviour, groups: &Groups) {
+        assert_eq!(groups.groups.concat().len(), 26);
+        assert_eq!(groups.groups.concat().iter().unique().count(), 26);
+        for (letters, &capacity) in groups.groups.iter().zip(behaviour.capacities.iter()) {
+            assert!(letters.len() <= capacity);
+        }
+    }
+
+    #[test]
+    fn should_keep_capacities() {
+        let behaviour = default_behaviour();
+
+        for _ in 0..100 {
+            let parent = behaviour.generate();
+            let child = behaviour.mutate(&parent);
+            let partner = behaviour.mutate(&parent);
+            let crossed = behaviour.cross(&child, &partner);
+
+            assert_groups(&behaviour, &parent);
+            assert_groups(&behaviour, &child);
+            assert_groups(&behaviour, &crossed);
+            assert_ne!(parent.groups, child.groups);
+            assert_eq!(child.parent_version, parent.version);
+        }
+    }
+
+    #[test]
+    fn should_prefer_lower_penalty() {
+        let behaviour = default_behaviour();
+        let mut a = behaviour.generate();
+        let mut b = behaviour.generate();
+        a.score = 1.;
+        b.score = 2.;
+
+        assert_eq!(behaviour.score_cmp(&a, &b), Ordering::Less);
+    }
+}
@@ -0,0 +1,216 @@
+use ed_balance::{get_id, Digraphs, IIndividual, IMutation};
+use itertools::Itertools;
+use std::hash::Hash;
+
+pub type GroupsPointer = Box<Groups>;
+
+#[derive(Debug, Hash, Eq, PartialEq, Clone, Copy)]
+pub enum Mutation {
+    /// Letters of different groups exchange them.
+    Swap { first: char, second: char },
+
+    /// A letter goes to another group.
+    Move { letter: char, group: usize },
+}
+
+impl IMutation for Mutation {}
+
+impl Mutation {
+    /// Returns `false` and keeps groups as is when the mutation does not fit them.
+    pub fn apply(&self, groups: &mut [Vec<char>], capacities: &[usize]) -> bool {
+        let find = |groups: &[Vec<char>], letter: char| {
+            groups.iter().enumerate().find_map(|(group, letters)| {
+                letters
+                    .iter()
+                    .position(|&x| x == letter)
+                    .map(|index| (group, index))
+            })
+        };
+
+        match *self {
+            Mutation::Swap { first, second } => match (find(groups, first), find(groups, second)) {
+                (Some((first_group, first_index)), Some((second_group, second_index)))
+                    if first_group != second_group =>
+                {
+                    groups[first_group][first_index] = second;
+                    groups[second_group][second_index] = first;
+                    true
+                }
+                _ => false,
+            },
+            Mutation::Move { letter, group } => match find(groups, letter) {
+                Some((from, index))
+                    if from != group
+                        && group < groups.len()
+                        && groups[group].len() < capacities[group] =>
+                {
+                    groups[from].remove(index);
+                    groups[group].push(letter);
+                    true
+                }
+                _ => false,
+            },
+        }
+    }
+
+    pub fn contains(&self, letter: char) -> bool {
+        match *self {
+            Mutation::Swap { first, second } => first == letter || second == letter,
+            Mutation::Move { letter: x, .. } => x == letter,
+        }
+    }
+}
+
+/// Letters split into `k` groups, like fingers or columns.
+#[derive(Clone)]
+pub struct Groups {
+    pub version: String,
+
+    /// Sorted letters of each group.
+    pub groups: Vec<Vec<char>>,
+
+    /// Digraphs scores of same group pairs, the penalty of each group.\
+    /// Pairs typed by the same finger are slow, so lower is better.
+    pub scores: Vec<f64>,
+    pub score: f64,
+    pub mutations: Vec<Mutation>,
+    pub parent_version: String,
+    pub parent: Vec<Vec<char>>,
+}
+
+impl IIndividual<Mutation> for Groups {
+    fn get_kind(&self) -> String {
+        self.parent_version.clone()
+    }
+
+    fn to_string(&self) -> String {
+        let groups = self
+            .groups
+            .iter()
+            .map(|x| x.iter().collect::<String>())
+            .join(" ");
+        let scores: String = self.scores.iter().map(|x| format!(" {:.3};", x)).collect();
+
+        format!(
+            "{}; {:.3};{} {};",
+            groups,
+            self.score,
+            scores,
+            get_id(&groups)
+        )
+    }
+
+    fn get_score(&self) -> f64 {
+        self.score
+    }
+}
+
+impl Groups {
+    pub fn new(
+        version: String,
+        groups: &[Vec<char>],
+        mutations: Vec<Mutation>,
+        parent_version: String,
+        parent: Vec<Vec<char>>,
+        digraphs: &Digraphs,
+    ) -> GroupsPointer {
+        let groups = groups
+            .iter()
+            .map(|x| x.iter().copied().sorted().collect_vec())
+            .collect_vec();
+        let scores = groups
+            .iter()
+            .map(|x| digraphs.calculate_score(x))
+            .collect_vec();
+
+        Box::new(Groups {
+            score: scores.iter().sum(),
+            groups,
+            scores,
+            version,
+            mutations,
+            parent_version,
+            parent,
+        })
+    }
+}
+
+impl Eq for Groups {}
+
+impl PartialEq for Groups {
+    fn eq(&self, other: &Groups) -> bool {
+        self.groups.eq(&other.groups)
+    }
+}
+
+impl Hash for Groups {
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: std::hash::Hasher,
+    {
+        self.groups.hash(state);
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn should_apply_mutations() {
+        let capacities = [2, 2, 1];
+        let mut groups = vec![vec!['a', 'b'], vec!['c'], vec![]];
+
+        assert!(Mutation::Swap {
+            first: 'a',
+            second: 'c'
+        }
+        .apply(&mut groups, &capacities));
+        assert_eq!(groups, vec![vec!['c', 'b'], vec!['a'], vec![]]);
+
+        assert!(Mutation::Move {
+            letter: 'b',
+            group: 2
+        }
+        .apply(&mut groups, &capacities));
+        assert_eq!(groups, vec![vec!['c'], vec!['a'], vec!['b']]);
+
+        // the group is full.
+        assert!(!Mutation::Move {
+            letter: 'a',
+            group: 2
+        }
+        .apply(&mut groups, &capacities));
+        // the same group.
+        assert!(!Mutation::Swap {
+            first: 'a',
+            second: 'a'
+        }
+        .apply(&mut groups, &capacities));
+        assert_eq!(groups, vec![vec!['c'], vec!['a'], vec!['b']]);
+    }
+
+    #[test]
+    fn should_score_same_group_pairs() {
+        let json = json!({ "ab": 2.0, "ba": 1.0, "cd": 4.0, "ac": 8.0 });
+        let digraphs = Digraphs::new(json.as_object().unwrap());
+
+        let actual = Groups::new(
+            "v".to_string(),
+            &[vec!['b', 'a'], vec!['c', 'd']],
+            Vec::new(),
+            "v".to_string(),
+            Vec::new(),
+            &digraphs,
+        );
+
+        assert_eq!(actual.groups, vec![vec!['a', 'b'], vec!['c', 'd']]);
+        assert_eq!(actual.scores, vec![3., 4.]);
+        assert_eq!(actual.score, 7.);
+        assert_eq!(
+            actual.to_string(),
+            "ab cd; 7.000; 3.000; 4.000; 5ea0cd0a12;"
+        );
+    }
+}
@@ -0,0 +1,5 @@
+mod behaviour;
+mod groups;
+
+pub use behaviour::*;
+pub use groups::*;
@@ -0,0 +1,19 @@
+mod calculation;
+
+use calculation::{Behaviour, Groups, Mutation};
+use ed_balance::{run, CliSettings};
+use std::process;
+use structopt::StructOpt;
+
+// split letters into `--groups` groups, like fingers.
+// letters of the same group can not be typed one after another quickly,
+// so digraphs of the same group are a penalty.
+// the genetic algorithm minimizes the sum of penalties.
+
+fn main() {
+    let args = CliSettings::from_args();
+    if let Err(e) = run::<Mutation, Groups, Behaviour>(args) {
+        eprintln!("Calculations failed: {:#?}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,626 @@
+use super::{Finger, Geometry, Position, Row, COLUMN_FINGERS, FINGERS_COUNT, THUMB};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+    path::Path,
+};
+
+/// Efforts are from `1` to `5`, `maxEffort` scales them.
+pub const MIN_EFFORT: f64 = 1.;
+pub const MAX_EFFORT: f64 = 5.;
+
+pub type EffortsConfig = BTreeMap<Position, BTreeMap<Position, f64>>;
+
+/// Settings of `keyboard.json`, `data/keyboard.schema.json` describes them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct KeyboardConfig {
+    #[serde(rename = "$schema", default)]
+    pub schema: Option<String>,
+
+    /// Letters on fixed positions, like `{ "a": "5", "s": 6 }`.
+    pub frozen: BTreeMap<char, FrozenPosition>,
+    pub blocked: Vec<Position>,
+    pub switch_penalty: f64,
+    pub same_key_penalty: f64,
+    pub max_effort: f64,
+
+    /// Efforts of the left part from a position to a position.
+    pub efforts: EffortsConfig,
+
+    /// Efforts of the right part, without them the right part mirrors the left one.
+    #[serde(default)]
+    pub right_efforts: Option<EffortsConfig>,
+    #[serde(default)]
+    pub effort_scales: EffortScales,
+
+    /// Fingers of the 15 left keys of the standard geometry.
+    #[serde(default)]
+    pub fingers: Option<BTreeMap<Position, Finger>>,
+    #[serde(default)]
+    pub geometry: Option<Vec<RowConfig>>,
+    #[serde(default)]
+    pub finger_loads: Option<Vec<f64>>,
+    #[serde(default)]
+    pub finger_penalty: f64,
+    #[serde(default = "one")]
+    pub inward_roll_weight: f64,
+    #[serde(default = "one")]
+    pub outward_roll_weight: f64,
+    #[serde(default = "one")]
+    pub same_finger_penalty: f64,
+    #[serde(default)]
+    pub same_finger_cap: Option<f64>,
+    #[serde(default)]
+    pub alternation_weight: f64,
+    #[serde(default)]
+    pub trigram_roll_weight: f64,
+    #[serde(default)]
+    pub redirect_weight: f64,
+    #[serde(default)]
+    pub one_hand_weight: f64,
+    #[serde(default)]
+    pub shift: ShiftConfig,
+    #[serde(default)]
+    pub thumbs: Option<ThumbsConfig>,
+}
+
+/// Older files keep positions of frozen keys as strings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FrozenPosition {
+    Number(Position),
+    Text(String),
+}
+
+/// Multipliers of efforts of each hand, they are applied after `maxEffort`,
+/// so efforts of a hand are from `scale` to `scale * maxEffort`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EffortScales {
+    #[serde(default = "one")]
+    pub left: f64,
+    #[serde(default = "one")]
+    pub right: f64,
+}
+
+impl Default for EffortScales {
+    fn default() -> Self {
+        EffortScales {
+            left: 1.,
+            right: 1.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Hand {
+    Left,
+    Right,
+}
+
+/// A row of keys, like `{ "hand": "right", "row": 0, "column": -1, "fingers": [0, 0, 1, 2, 3, 3] }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RowConfig {
+    pub hand: Hand,
+    pub row: u8,
+    #[serde(default)]
+    pub column: i8,
+    pub fingers: Vec<Finger>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ShiftConfig {
+    #[serde(default)]
+    pub left: Option<f64>,
+    #[serde(default)]
+    pub right: Option<f64>,
+    #[serde(default)]
+    pub coordination: f64,
+    #[serde(default = "one")]
+    pub same_hand_penalty: f64,
+}
+
+impl Default for ShiftConfig {
+    fn default() -> Self {
+        ShiftConfig {
+            left: None,
+            right: None,
+            coordination: 0.,
+            same_hand_penalty: 1.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpaceThumb {
+    Left,
+    Right,
+    Either,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThumbsConfig {
+    pub space: SpaceThumb,
+    #[serde(default = "one")]
+    pub effort: f64,
+
+    /// Thumb keys which can have letters, they can not have the space.
+    #[serde(default)]
+    pub letters: Vec<Position>,
+}
+
+fn one() -> f64 {
+    1.
+}
+
+impl KeyboardConfig {
+    pub fn load(path: &Path) -> Result<KeyboardConfig, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}.", path.display(), e))?;
+
+        serde_json::from_str(&content).map_err(|e| format!("{}: {}.", path.display(), e))
+    }
+
+    /// The geometry of the settings and all errors of them, one per line.
+    pub fn validate(&self) -> Result<Geometry, String> {
+        let geometry = self.get_geometry()?;
+        let mut errors = Vec::new();
+
+        self.validate_efforts(&geometry, &mut errors);
+        self.validate_positions(&geometry, &mut errors);
+        self.validate_finger_loads(&mut errors);
+
+        if errors.is_empty() {
+            Ok(geometry)
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+
+    /// `geometry`, the standard geometry with `fingers` or the default one.
+    pub fn get_geometry(&self) -> Result<Geometry, String> {
+        match (&self.geometry, &self.fingers) {
+            (Some(rows), _) => load_geometry(rows),
+            (None, Some(fingers)) => load_standard_geometry(fingers),
+            (None, None) => Ok(Geometry::default()),
+        }
+    }
+
+    /// Positions of frozen keys, positions which are not numbers are skipped.
+    pub fn get_frozen(&self) -> impl Iterator<Item = (char, Position)> + '_ {
+        self.frozen
+            .iter()
+            .filter_map(|(&letter, position)| Some((letter, position.parse()?)))
+    }
+
+    /// Blocked keys and thumbs unless letters can take them.
+    pub fn get_blocked(&self, geometry: &Geometry) -> HashSet<Position> {
+        let thumb_letters = self.thumbs.as_ref().map(|x| x.letters.as_slice());
+
+        self.blocked
+            .iter()
+            .copied()
+            .chain(geometry.positions().filter(|&x| geometry.is_thumb(x)))
+            .filter(|x| !thumb_letters.unwrap_or(&[]).contains(x))
+            .collect()
+    }
+
+    /// `efforts` and `rightEfforts` with their names.
+    fn get_efforts(&self) -> impl Iterator<Item = (&str, &EffortsConfig)> {
+        Some(("efforts", &self.efforts))
+            .into_iter()
+            .chain(self.right_efforts.as_ref().map(|x| ("rightEfforts", x)))
+    }
+
+    fn validate_efforts(&self, geometry: &Geometry, errors: &mut Vec<String>) {
+        let mut values = vec![("maxEffort".to_string(), self.max_effort)];
+        if let Some(thumbs) = &self.thumbs {
+            values.push(("The thumbs effort".to_string(), thumbs.effort));
+        }
+
+        for (name, efforts) in self.get_efforts() {
+            for (from, efforts) in efforts {
+                values.extend(efforts.iter().map(|(to, &value)| {
+                    let name = format!("The effort of {} from {} to {}", name, from, to);
+                    (name, value)
+                }));
+            }
+        }
+
+        errors.extend(
+            values
+                .into_iter()
+                .filter(|(_, value)| !(MIN_EFFORT..=MAX_EFFORT).contains(value))
+                .map(|(name, value)| {
+                    format!(
+                        "{} is {}, it must be from {} to {}.",
+                        name, value, MIN_EFFORT, MAX_EFFORT
+                    )
+                }),
+        );
+
+        let scales = &self.effort_scales;
+        for (hand, scale) in [("left", scales.left), ("right", scales.right)] {
+            if scale.is_nan() || scale <= 0. {
+                errors.push(format!(
+                    "effortScales.{} is {}, it must be positive.",
+                    hand, scale
+                ));
+            }
+        }
+
+        for (name, efforts) in self.get_efforts() {
+            let outside = efforts
+                .iter()
+                .flat_map(|(&from, x)| x.keys().map(move |&to| (from, to)))
+                .find(|&(from, to)| !geometry.contains(from) || !geometry.contains(to));
+
+            if let Some((from, to)) = outside {
+                errors.push(format!(
+                    "The effort of {} from {} to {} is outside of {} keys.",
+                    name,
+                    from,
+                    to,
+                    geometry.positions().count()
+                ));
+            }
+        }
+    }
+
+    fn validate_positions(&self, geometry: &Geometry, errors: &mut Vec<String>) {
+        let count = geometry.positions().count();
+        let blocked = self.get_blocked(geometry);
+
+        for &position in self.blocked.iter().filter(|&&x| !geometry.contains(x)) {
+            errors.push(format!(
+                "The blocked position {} is outside of {} keys.",
+                position, count
+            ));
+        }
+
+        let mut letters: BTreeMap<Position, char> = BTreeMap::new();
+        for (&letter, position) in &self.frozen {
+            let position = match position.parse() {
+                Some(x) => x,
+                None => {
+                    errors.push(format!(
+                        "'{}' is frozen on {}, it is not a position.",
+                        letter, position
+                    ));
+                    continue;
+                }
+            };
+
+            if !geometry.contains(position) {
+                errors.push(format!(
+                    "'{}' is frozen on the position {} outside of {} keys.",
+                    letter, position, count
+                ));
+            } else if blocked.contains(&position) {
+                errors.push(format!(
+                    "'{}' is frozen on the blocked position {}.",
+                    letter, position
+                ));
+            }
+
+            if let Some(other) = letters.insert(position, letter) {
+                errors.push(format!(
+                    "'{}' and '{}' are frozen on the same position {}.",
+                    other, letter, position
+                ));
+            }
+        }
+
+        let thumbs = match &self.thumbs {
+            Some(thumbs) => thumbs,
+            None => return,
+        };
+
+        let space = match get_space(thumbs.space, geometry) {
+            Some(space) => space,
+            None => {
+                errors.push(format!(
+                    "The geometry does not have a thumb for the {:?} space.",
+                    thumbs.space
+                ));
+                return;
+            }
+        };
+
+        for &position in &thumbs.letters {
+            if !geometry.contains(position) || !geometry.is_thumb(position) {
+                errors.push(format!(
+                    "The thumb letters position {} is not a thumb.",
+                    position
+                ));
+            } else if space.contains(&position) {
+                errors.push(format!(
+                    "The thumb letters position {} has the space.",
+                    position
+                ));
+            }
+        }
+    }
+
+    fn validate_finger_loads(&self, errors: &mut Vec<String>) {
+        let loads = match &self.finger_loads {
+            Some(loads) => loads,
+            None => return,
+        };
+
+        if loads.len() != FINGERS_COUNT {
+            errors.push(format!(
+                "fingerLoads has {} values, it must have {}.",
+                loads.len(),
+                FINGERS_COUNT
+            ));
+        }
+
+        if loads.iter().any(|&x| x < 0.) || loads.iter().sum::<f64>() <= 0. {
+            errors.push("fingerLoads must be positive.".to_string());
+        }
+    }
+}
+
+impl fmt::Display for FrozenPosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrozenPosition::Number(x) => write!(f, "{}", x),
+            FrozenPosition::Text(x) => write!(f, "{:?}", x),
+        }
+    }
+}
+
+impl FrozenPosition {
+    pub fn parse(&self) -> Option<Position> {
+        match self {
+            FrozenPosition::Number(x) => Some(*x),
+            FrozenPosition::Text(x) => x.parse().ok(),
+        }
+    }
+}
+
+/// The space is on the first thumb key of its hand, the left and the right one,
+/// `either` takes the thumb of the hand which does not type the neighbour letter.
+pub fn get_space(space: SpaceThumb, geometry: &Geometry) -> Option<[Position; 2]> {
+    let left = geometry.get_thumb(true);
+    let right = geometry.get_thumb(false);
+
+    Some(match space {
+        SpaceThumb::Left => [left?, left?],
+        SpaceThumb::Right => [right?, right?],
+        SpaceThumb::Either => [right?, left?],
+    })
+}
+
+/// Fingers go from the outer key, `0` is the pinky, `3` is the index finger, `4` is the thumb.\
+/// The column is of the outer key, `0` is the column of the pinky,
+/// it is needed for keys outside of it to mirror efforts of the left part.
+fn load_geometry(rows: &[RowConfig]) -> Result<Geometry, String> {
+    for (index, row) in rows.iter().enumerate() {
+        if let Some(finger) = row.fingers.iter().find(|&&x| x > THUMB) {
+            return Err(format!(
+                "The geometry row {} has the finger {}, fingers are from 0 to {}.",
+                index, finger, THUMB
+            ));
+        }
+    }
+
+    let count: usize = rows.iter().map(|x| x.fingers.len()).sum();
+    if count > Position::MAX as usize {
+        return Err(format!(
+            "The geometry has {} keys, it can have {} keys.",
+            count,
+            Position::MAX
+        ));
+    }
+
+    Ok(Geometry::new(
+        rows.iter()
+            .map(|row| Row {
+                left: row.hand == Hand::Left,
+                row: row.row,
+                column: row.column,
+                fingers: row.fingers.clone(),
+            })
+            .collect(),
+    ))
+}
+
+/// All 15 positions of the left part must have a finger of the left hand,
+/// the right part mirrors them.
+fn load_standard_geometry(fingers: &BTreeMap<Position, Finger>) -> Result<Geometry, String> {
+    let mut rows = [COLUMN_FINGERS; 3];
+    for position in 0..15 {
+        let finger = fingers
+            .get(&position)
+            .ok_or(format!("fingers does not have the position {}.", position))?;
+        if *finger >= THUMB {
+            return Err(format!(
+                "fingers has the finger {} for the position {}, fingers are from 0 to 3.",
+                finger, position
+            ));
+        }
+
+        rows[position as usize / 5][position as usize % 5] = *finger;
+    }
+
+    if let Some(position) = fingers.keys().find(|&&x| x >= 15) {
+        return Err(format!(
+            "fingers has the position {}, it has positions of the left part from 0 to 14.",
+            position
+        ));
+    }
+
+    Ok(Geometry::standard(&rows))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    pub fn parse(mut changes: Value) -> Result<KeyboardConfig, serde_json::Error> {
+        let mut json = json!({
+            "frozen": { "a": "5", "s": 6 },
+            "blocked": [15],
+            "switchPenalty": 5,
+            "sameKeyPenalty": 1.5,
+            "maxEffort": 3,
+            "efforts": { "0": { "0": 1, "1": 2 }, "1": { "0": 3, "1": 4 } },
+        });
+        json.as_object_mut()
+            .unwrap()
+            .append(changes.as_object_mut().unwrap());
+
+        serde_json::from_value(json)
+    }
+
+    #[test]
+    fn should_parse_defaults() {
+        let config = parse(json!({})).unwrap();
+
+        assert_eq!(
+            config.get_frozen().collect::<Vec<_>>(),
+            vec![('a', 5), ('s', 6)]
+        );
+        assert_eq!(config.effort_scales, EffortScales::default());
+        assert_eq!(config.shift, ShiftConfig::default());
+        assert_eq!(config.inward_roll_weight, 1.);
+        assert_eq!(config.thumbs, None);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn should_reject_unknown_and_missing_fields() {
+        let error = parse(json!({ "switchPenalti": 5 })).unwrap_err();
+        assert!(error.to_string().contains("unknown field `switchPenalti`"));
+
+        let mut json = serde_json::to_value(parse(json!({})).unwrap()).unwrap();
+        json.as_object_mut().unwrap().remove("maxEffort");
+        let error = serde_json::from_value::<KeyboardConfig>(json).unwrap_err();
+        assert!(error.to_string().contains("missing field `maxEffort`"));
+    }
+
+    #[test]
+    fn should_report_all_errors() {
+        let config = parse(json!({
+            "frozen": { "a": "15", "b": 40, "c": "x", "d": "1", "e": 1 },
+            "blocked": [15, 33],
+            "maxEffort": 6,
+            "efforts": { "0": { "0": 0.5 }, "32": { "0": 1 } },
+            "effortScales": { "right": 0 },
+            "thumbs": { "space": "either", "letters": [30] },
+        }))
+        .unwrap();
+
+        assert_eq!(
+            config.validate().unwrap_err(),
+            [
+                "maxEffort is 6, it must be from 1 to 5.",
+                "The effort of efforts from 0 to 0 is 0.5, it must be from 1 to 5.",
+                "effortScales.right is 0, it must be positive.",
+                "The effort of efforts from 32 to 0 is outside of 32 keys.",
+                "The blocked position 33 is outside of 32 keys.",
+                "'a' is frozen on the blocked position 15.",
+                "'b' is frozen on the position 40 outside of 32 keys.",
+                "'c' is frozen on \"x\", it is not a position.",
+                "'d' and 'e' are frozen on the same position 1.",
+                "The thumb letters position 30 has the space.",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn should_block_thumbs_without_letters() {
+        let config = parse(json!({
+            "frozen": { "a": 31 },
+            "thumbs": { "space": "left", "letters": [31] },
+        }))
+        .unwrap();
+        let geometry = config.validate().unwrap();
+
+        let blocked = config.get_blocked(&geometry);
+        assert!(blocked.contains(&15) && blocked.contains(&30) && !blocked.contains(&31));
+    }
+
+    #[test]
+    fn should_load_geometry() {
+        let rows = json!([
+            { "hand": "left", "row": 0, "fingers": [0, 1, 2, 3, 3] },
+            { "hand": "right", "row": 0, "column": -1, "fingers": [0, 0, 1, 2, 3, 3] },
+            { "hand": "left", "row": 1, "fingers": [4, 4] },
+        ]);
+        let config = parse(json!({ "geometry": rows, "blocked": [] })).unwrap();
+        let geometry = config.get_geometry().unwrap();
+
+        assert_eq!(geometry.positions().count(), 13);
+        assert!(!geometry.is_left(5) && geometry.is_thumb(12));
+        assert_eq!(geometry.get_fingers()[&5], 7);
+        assert!(
+            parse(json!({ "geometry": [{ "hand": "up", "row": 0, "fingers": [0] }] })).is_err()
+        );
+
+        let config = parse(json!({ "geometry": [{ "hand": "left", "row": 0, "fingers": [5] }] }));
+        assert_eq!(
+            config.unwrap().get_geometry(),
+            Err("The geometry row 0 has the finger 5, fingers are from 0 to 4.".to_string())
+        );
+    }
+
+    #[test]
+    fn should_load_fingers() {
+        let mut fingers = json!({});
+        for position in 0..15 {
+            fingers[position.to_string()] = json!(position / 4);
+        }
+
+        let config = parse(json!({ "fingers": fingers.clone() })).unwrap();
+        assert_eq!(config.get_geometry().unwrap().get_fingers()[&14], 3);
+
+        fingers["15"] = json!(0);
+        let config = parse(json!({ "fingers": fingers })).unwrap();
+        assert!(config.get_geometry().is_err());
+    }
+
+    #[test]
+    fn should_validate_files() {
+        for path in &[
+            "data/keyboard.json",
+            "data/geometries/ansi.json",
+            "data/geometries/split-36.json",
+            "data/geometries/3x6-thumbs.json",
+        ] {
+            let config = KeyboardConfig::load(Path::new(path)).unwrap();
+
+            assert!(config.validate().is_ok(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn should_describe_all_settings_in_schema() {
+        let schema: Value =
+            serde_json::from_str(&std::fs::read_to_string("data/keyboard.schema.json").unwrap())
+                .unwrap();
+        let config = serde_json::to_value(parse(json!({})).unwrap()).unwrap();
+
+        let mut expected = config.as_object().unwrap().keys().collect::<Vec<_>>();
+        let mut actual = schema["properties"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .collect::<Vec<_>>();
+        expected.sort();
+        actual.sort();
+
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,167 @@
+use super::{Words, SPACE};
+use itertools::Itertools;
+use std::{collections::HashMap, hash::Hash, iter::once};
+
+/// Counts of a text which keyboard scores depend on.\
+/// A score is a sum over letters, pairs and triples of letters,
+/// so they are counted once instead of walking all words for each keyboard.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Corpus {
+    /// First letters of words, they start typing without a previous key.
+    pub initials: Vec<(char, u64)>,
+    pub letters: Vec<(char, u64)>,
+    pub bigrams: Vec<((char, char), u64)>,
+    pub trigrams: Vec<((char, char, char), u64)>,
+
+    /// Upper case letters, they need the shift.\
+    /// Other counts have them in lower case, like keys of a keyboard.
+    pub capitals: Vec<(char, u64)>,
+}
+
+impl Corpus {
+    /// Words are typed apart, each of them starts from its first letter.
+    pub fn new(words: &Words) -> Corpus {
+        Corpus::count(words, false)
+    }
+
+    /// Words are typed one by one with spaces between them.\
+    /// A list of words does not keep their order, so the next word is any word
+    /// in proportion to its count, it gives trigrams around spaces.
+    pub fn with_spaces(words: &Words) -> Corpus {
+        Corpus::count(words, true)
+    }
+
+    fn count(words: &Words, spaces: bool) -> Corpus {
+        let mut initials = HashMap::new();
+        let mut letters = HashMap::new();
+        let mut bigrams = HashMap::new();
+        let mut trigrams = HashMap::new();
+        let mut capitals = HashMap::new();
+        let mut finals: HashMap<char, u64> = HashMap::new();
+
+        for (word, count) in words {
+            let chars = word.chars().map(to_lowercase).collect_vec();
+            let (first, last) = match (chars.first(), chars.last()) {
+                (Some(&first), Some(&last)) => (first, last),
+                _ => continue,
+            };
+
+            for letter in word.chars().filter(|x| x.is_uppercase()) {
+                *capitals.entry(to_lowercase(letter)).or_default() += count;
+            }
+
+            *initials.entry(first).or_default() += count;
+            *finals.entry(last).or_default() += count;
+
+            // a word takes the space before it, the one after it is of the next word.
+            let chars = if spaces {
+                *letters.entry(SPACE).or_default() += count;
+                once(SPACE).chain(chars).chain(once(SPACE)).collect_vec()
+            } else {
+                chars
+            };
+
+            for &letter in chars.iter().filter(|&&x| x != SPACE) {
+                *letters.entry(letter).or_default() += count;
+            }
+            for (&a, &b) in chars.iter().tuple_windows() {
+                *bigrams.entry((a, b)).or_default() += count;
+            }
+            for (&a, &b, &c) in chars.iter().tuple_windows() {
+                *trigrams.entry((a, b, c)).or_default() += count;
+            }
+        }
+
+        if spaces {
+            let total: u128 = initials.values().map(|&x| x as u128).sum();
+            for ((&last, &a), (&first, &b)) in finals.iter().cartesian_product(initials.iter()) {
+                let count = ((a as u128 * b as u128 + total / 2) / total) as u64;
+                if count > 0 {
+                    *trigrams.entry((last, SPACE, first)).or_default() += count;
+                }
+            }
+
+            // typing does not start from words.
+            initials.clear();
+        }
+
+        Corpus {
+            initials: sort_counts(initials),
+            letters: sort_counts(letters),
+            bigrams: sort_counts(bigrams),
+            trigrams: sort_counts(trigrams),
+            capitals: sort_counts(capitals),
+        }
+    }
+}
+
+fn to_lowercase(letter: char) -> char {
+    letter.to_lowercase().next().unwrap_or(letter)
+}
+
+/// The order of sums must not change between runs to have the same scores.
+fn sort_counts<T: Ord + Hash>(counts: HashMap<T, u64>) -> Vec<(T, u64)> {
+    counts.into_iter().sorted().collect()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn should_count_parts_of_words() {
+        let words = vec![("abab".to_string(), 2), ("b".to_string(), 1)];
+
+        let actual = Corpus::new(&words);
+
+        assert_eq!(actual.initials, vec![('a', 2), ('b', 1)]);
+        assert_eq!(actual.letters, vec![('a', 4), ('b', 5)]);
+        assert_eq!(actual.bigrams, vec![(('a', 'b'), 4), (('b', 'a'), 2)]);
+        assert_eq!(
+            actual.trigrams,
+            vec![(('a', 'b', 'a'), 2), (('b', 'a', 'b'), 2)]
+        );
+        assert!(actual.capitals.is_empty());
+    }
+
+    #[test]
+    fn should_count_capitals_separately() {
+        let words = vec![("Abc".to_string(), 2), ("abC".to_string(), 1)];
+
+        let actual = Corpus::new(&words);
+
+        assert_eq!(actual.initials, vec![('a', 3)]);
+        assert_eq!(actual.bigrams, vec![(('a', 'b'), 3), (('b', 'c'), 3)]);
+        assert_eq!(actual.capitals, vec![('a', 2), ('c', 1)]);
+    }
+
+    #[test]
+    fn should_count_spaces_between_words() {
+        let words = vec![("ab".to_string(), 3), ("b".to_string(), 1)];
+
+        let actual = Corpus::with_spaces(&words);
+
+        assert!(actual.initials.is_empty());
+        assert_eq!(actual.letters, vec![(' ', 4), ('a', 3), ('b', 4)]);
+        assert_eq!(
+            actual.bigrams,
+            vec![
+                ((' ', 'a'), 3),
+                ((' ', 'b'), 1),
+                (('a', 'b'), 3),
+                (('b', ' '), 4)
+            ]
+        );
+        // 4 words end with "b", 3 of 4 next words start with "a".
+        assert_eq!(
+            actual.trigrams,
+            vec![
+                ((' ', 'a', 'b'), 3),
+                ((' ', 'b', ' '), 1),
+                (('a', 'b', ' '), 3),
+                (('b', ' ', 'a'), 3),
+                (('b', ' ', 'b'), 1)
+            ]
+        );
+    }
+}
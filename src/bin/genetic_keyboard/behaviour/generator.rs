@@ -1,16 +1,23 @@
-use super::{score_calculator::calculate_score, Behaviour, FrozenKeys, Keyboard, Position};
+use super::{
+    get_hand, score_calculator::calculate_score, Behaviour, FrozenKeys, Geometry, Keyboard,
+    Position,
+};
 use crate::keyboard::Keys;
-use ed_balance::get_version;
+use ed_balance::{get_version, Split};
 use itertools::Itertools;
 use rand::prelude::SliceRandom;
 use std::collections::HashSet;
 
 pub fn generate(this: &Behaviour) -> Box<Keyboard> {
     let version = get_version();
-    let keys = generate_keys(&this.frozen_keys, &this.blocked_keys);
+    let keys = generate_keys(
+        &this.geometry,
+        &this.frozen_keys,
+        &this.blocked_keys,
+        this.hands.as_ref(),
+    );
 
     debug_assert_eq!(keys.len(), 26);
-    debug_assert_eq!(keys.values().max().unwrap(), &29_u8);
 
     Keyboard::new(
         version.clone(),
@@ -19,28 +26,43 @@ pub fn generate(this: &Behaviour) -> Box<Keyboard> {
         Vec::new(),
         version, // versions match to be able cross children with parents
         keys,
+        this.geometry.clone(),
     )
 }
 
-fn generate_keys(frozen_keys: &FrozenKeys, blocked_keys: &HashSet<Position>) -> Keys {
+/// Letters with a hand take positions of the hand, the rest take any free ones.
+fn generate_keys(
+    geometry: &Geometry,
+    frozen_keys: &FrozenKeys,
+    blocked_keys: &HashSet<Position>,
+    hands: Option<&Split>,
+) -> Keys {
     let rnd = &mut rand::thread_rng();
-    let mut letters = ('a'..='z')
+    let (assigned, mut letters): (Vec<_>, Vec<_>) = ('a'..='z')
         .filter(|x| !frozen_keys.contains_key(x))
-        .collect_vec();
+        .partition(|&x| get_hand(hands, x).is_some());
     letters.shuffle(rnd);
 
     let frozen_values: HashSet<_> = frozen_keys.values().cloned().collect();
-    let mut positions = (0..=29 as Position)
+    let mut positions = geometry
+        .positions()
         .filter(|x| !blocked_keys.contains(x))
         .filter(|x| !frozen_values.contains(x))
         .collect_vec();
     positions.shuffle(rnd);
 
-    letters
-        .into_iter()
-        .zip(positions.into_iter())
-        .merge(frozen_keys.clone())
-        .collect()
+    let mut keys: Keys = frozen_keys.clone();
+    for letter in assigned {
+        let left = get_hand(hands, letter) == Some(true);
+        let index = positions
+            .iter()
+            .position(|&x| geometry.is_left(x) == left)
+            .expect("The hand does not have enough keys.");
+        keys.insert(letter, positions.remove(index));
+    }
+
+    keys.extend(letters.into_iter().zip(positions));
+    keys
 }
 
 #[cfg(test)]
@@ -51,9 +73,9 @@ pub mod tests {
     fn test_generate_with_no_frozen() {
         let frozen_keys: FrozenKeys = [].iter().cloned().collect();
         let frozen_values: HashSet<_> = frozen_keys.values().cloned().collect();
-        let blocked_keys: HashSet<Position> = [9, 14, 19, 24].iter().cloned().collect();
+        let blocked_keys: HashSet<Position> = [9, 14, 19, 24, 30, 31].iter().cloned().collect();
 
-        let keys = generate_keys(&frozen_keys, &blocked_keys);
+        let keys = generate_keys(&Geometry::default(), &frozen_keys, &blocked_keys, None);
 
         let expected_keys = ('a'..='z').collect_vec();
         let actual_keys = keys
@@ -87,9 +109,9 @@ pub mod tests {
             .cloned()
             .collect();
         let frozen_values: HashSet<_> = frozen_keys.values().cloned().collect();
-        let blocked_keys: HashSet<Position> = [0, 2, 15, 16, 17].iter().cloned().collect();
+        let blocked_keys: HashSet<Position> = [0, 2, 15, 16, 17, 30, 31].iter().cloned().collect();
 
-        let keys = generate_keys(&frozen_keys, &blocked_keys);
+        let keys = generate_keys(&Geometry::default(), &frozen_keys, &blocked_keys, None);
 
         let expected_keys = ('a'..='z').collect_vec();
         let actual_keys = keys
@@ -118,4 +140,35 @@ pub mod tests {
         assert_eq!(actual_keys, expected_keys);
         assert_eq!(actual_values, expected_values);
     }
+
+    #[test]
+    fn should_put_letters_on_free_thumbs() {
+        let frozen_keys: FrozenKeys = [('e', 31_u8)].iter().cloned().collect();
+        // 26 positions with the right thumb.
+        let blocked_keys: HashSet<Position> = [0, 4, 5, 9, 30].iter().cloned().collect();
+
+        let keys = generate_keys(&Geometry::default(), &frozen_keys, &blocked_keys, None);
+
+        assert_eq!(keys.len(), 26);
+        assert_eq!(keys[&'e'], 31);
+        assert!(keys.values().all(|x| !blocked_keys.contains(x)));
+    }
+
+    #[test]
+    fn should_respect_hands() {
+        let frozen_keys: FrozenKeys = [('a', 1_u8)].iter().cloned().collect();
+        let blocked_keys: HashSet<Position> = [9, 14, 19, 24].iter().cloned().collect();
+        let hands: Split = "abcdefghijkl mnopqrstuvw".parse().unwrap();
+        let geometry = Geometry::default();
+
+        for _ in 0..20 {
+            let keys = generate_keys(&geometry, &frozen_keys, &blocked_keys, Some(&hands));
+
+            assert_eq!(keys.len(), 26);
+            assert_eq!(keys.values().unique().count(), 26);
+            assert_eq!(keys[&'a'], 1);
+            assert!(hands.left.iter().all(|x| geometry.is_left(keys[x])));
+            assert!(hands.right.iter().all(|x| !geometry.is_left(keys[x])));
+        }
+    }
 }
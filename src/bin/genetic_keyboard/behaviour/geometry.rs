@@ -0,0 +1,310 @@
+use super::{Finger, Fingers, Position, FINGERS_COUNT};
+use crate::keyboard::Keys;
+use itertools::Itertools;
+use std::collections::HashMap;
+
+/// Fingers of a hand from the pinky `0` to the index finger `3`, thumbs are `4`.
+pub const THUMB: Finger = 4;
+
+/// Fingers of the standard geometry by columns from the outer one, index fingers have two columns.
+pub const COLUMN_FINGERS: [Finger; 5] = [0, 1, 2, 3, 3];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Key {
+    pub left: bool,
+
+    /// Rows of a hand from the top one.
+    pub row: u8,
+
+    /// Columns from the pinky one to the index finger,
+    /// extra keys outside of the pinky column are below `0`.
+    pub column: i8,
+
+    /// A finger of the hand, `THUMB` for thumbs.
+    pub finger: Finger,
+}
+
+/// A row of keys of a hand from the outer one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    pub left: bool,
+    pub row: u8,
+
+    /// The column of the outer key.
+    pub column: i8,
+    pub fingers: Vec<Finger>,
+}
+
+/// Physical keys of a keyboard, positions are numbered in the order of rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Geometry {
+    keys: Vec<Key>,
+
+    /// Positions of each row from the outer key.
+    rows: Vec<Vec<Position>>,
+}
+
+impl Default for Geometry {
+    /// Three rows of five keys for each hand and a thumb key below them,
+    /// 0-14 the left part, 15-29 the right part, 30 and 31 the left and the right thumbs.
+    fn default() -> Self {
+        Geometry::standard(&[COLUMN_FINGERS; 3])
+    }
+}
+
+impl Geometry {
+    pub fn new(rows: Vec<Row>) -> Geometry {
+        let mut keys = Vec::new();
+        let rows = rows
+            .into_iter()
+            .map(|row| {
+                (0..row.fingers.len())
+                    .map(|index| {
+                        keys.push(Key {
+                            left: row.left,
+                            row: row.row,
+                            column: row.column + index as i8,
+                            finger: row.fingers[index],
+                        });
+                        (keys.len() - 1) as Position
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        Geometry { keys, rows }
+    }
+
+    /// The standard geometry with fingers of rows of the left part, the right part mirrors them.
+    pub fn standard(fingers: &[[Finger; 5]; 3]) -> Geometry {
+        let letter_rows = |left: bool| {
+            fingers.iter().enumerate().map(move |(row, fingers)| Row {
+                left,
+                row: row as u8,
+                column: 0,
+                fingers: fingers.to_vec(),
+            })
+        };
+        let thumb_row = |left: bool| Row {
+            left,
+            row: 3,
+            column: 0,
+            fingers: vec![THUMB],
+        };
+
+        Geometry::new(
+            letter_rows(true)
+                .chain(letter_rows(false))
+                .chain([thumb_row(true), thumb_row(false)].iter().cloned())
+                .collect(),
+        )
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Position> {
+        0..self.keys.len() as Position
+    }
+
+    pub fn contains(&self, position: Position) -> bool {
+        (position as usize) < self.keys.len()
+    }
+
+    pub fn is_left(&self, position: Position) -> bool {
+        self.keys[position as usize].left
+    }
+
+    pub fn is_thumb(&self, position: Position) -> bool {
+        self.keys[position as usize].finger == THUMB
+    }
+
+    pub fn get_row(&self, position: Position) -> u8 {
+        self.keys[position as usize].row
+    }
+
+    /// Fingers of all keys except thumbs from the left pinky `0` to the right pinky `7`.
+    pub fn get_fingers(&self) -> Fingers {
+        self.positions()
+            .filter(|&x| !self.is_thumb(x))
+            .map(|x| {
+                let key = &self.keys[x as usize];
+                let finger = if key.left {
+                    key.finger
+                } else {
+                    FINGERS_COUNT as Finger - 1 - key.finger
+                };
+                (x, finger)
+            })
+            .collect()
+    }
+
+    /// The key of the other hand at the same row and column.
+    pub fn mirror(&self, position: Position) -> Option<Position> {
+        let key = &self.keys[position as usize];
+
+        self.positions().find(|&x| {
+            let other = &self.keys[x as usize];
+            other.left != key.left && other.row == key.row && other.column == key.column
+        })
+    }
+
+    /// The first thumb key of a hand, it has the space.
+    pub fn get_thumb(&self, left: bool) -> Option<Position> {
+        self.positions()
+            .find(|&x| self.is_thumb(x) && self.is_left(x) == left)
+    }
+
+    /// Rows of the left part, then mirrored rows of the right part,
+    /// then thumbs when they have letters.\
+    /// Like `jpmyq asreb zxcvk  whtu_ dnloi gf___  _e`, `_` is a key without a letter.
+    pub fn format_layout(&self, keys: &Keys) -> String {
+        let letters: HashMap<_, _> = keys.iter().map(|(&c, &p)| (p, c)).collect();
+        let letter = |x: &Position| letters.get(x).copied().unwrap_or('_');
+
+        let left = self
+            .get_groups(true)
+            .iter()
+            .map(|x| x.iter().map(letter).collect::<String>())
+            .join(" ");
+        let right = self
+            .get_groups(false)
+            .iter()
+            .map(|x| x.iter().map(letter).collect::<String>())
+            .join(" ");
+        let thumbs = self.get_thumbs();
+
+        // the space is not a letter, layouts without letters on thumbs skip them.
+        if !thumbs.iter().any(|x| letters.contains_key(x)) {
+            return format!("{}  {}", left, right);
+        }
+
+        format!(
+            "{}  {}  {}",
+            left,
+            right,
+            thumbs.iter().map(letter).collect::<String>()
+        )
+    }
+
+    /// Parses a layout of `format_layout`, `None` when it does not fit the geometry.
+    pub fn parse_layout(&self, line: &str) -> Option<Keys> {
+        let line = line.split(';').next()?;
+        let parts = line.split_whitespace().collect_vec();
+        let mut groups = self.get_groups(true);
+        groups.extend(self.get_groups(false));
+
+        if parts.len() == groups.len() + 1 {
+            groups.push(self.get_thumbs());
+        }
+
+        if parts.len() != groups.len() {
+            return None;
+        }
+
+        let mut keys = Keys::new();
+        for (part, positions) in parts.iter().zip(groups) {
+            if part.chars().count() != positions.len() {
+                return None;
+            }
+
+            keys.extend(part.chars().zip(positions).filter(|(c, _)| c != &'_'));
+        }
+
+        Some(keys)
+    }
+
+    /// Rows of a hand without thumbs as they are shown, right rows go from the inner key.
+    fn get_groups(&self, left: bool) -> Vec<Vec<Position>> {
+        self.rows
+            .iter()
+            .filter(|row| row.first().is_some_and(|&x| self.is_left(x) == left))
+            .map(|row| {
+                let keys = row.iter().copied().filter(|&x| !self.is_thumb(x));
+                if left {
+                    keys.collect_vec()
+                } else {
+                    keys.rev().collect_vec()
+                }
+            })
+            .filter(|x| !x.is_empty())
+            .collect()
+    }
+
+    /// Thumbs of the left hand, then thumbs of the right hand from the inner key.
+    fn get_thumbs(&self) -> Vec<Position> {
+        let thumbs = |left: bool| {
+            self.rows
+                .iter()
+                .flatten()
+                .copied()
+                .filter(move |&x| self.is_thumb(x) && self.is_left(x) == left)
+                .collect_vec()
+        };
+
+        thumbs(true)
+            .into_iter()
+            .chain(thumbs(false).into_iter().rev())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn should_build_standard_geometry() {
+        let geometry = Geometry::default();
+
+        assert_eq!(geometry.positions().count(), 32);
+        assert!(geometry.is_left(14) && !geometry.is_left(15));
+        assert!(geometry.is_left(30) && !geometry.is_left(31));
+        assert_eq!(geometry.get_row(12), 2);
+        assert_eq!(geometry.get_row(31), 3);
+        assert_eq!(geometry.mirror(3), Some(18));
+        assert_eq!(geometry.mirror(30), Some(31));
+        assert_eq!(geometry.get_thumb(false), Some(31));
+
+        let fingers = geometry.get_fingers();
+        assert_eq!(fingers.len(), 30);
+        assert_eq!(fingers[&5], 0);
+        assert_eq!(fingers[&9], 3);
+        assert_eq!(fingers[&20], 7);
+        assert_eq!(fingers[&24], 4);
+    }
+
+    #[test]
+    fn should_format_and_parse_layouts() {
+        // a row-staggered right hand with extra outer keys.
+        let geometry = Geometry::new(vec![
+            Row {
+                left: true,
+                row: 0,
+                column: 0,
+                fingers: vec![0, 1, 2, 3],
+            },
+            Row {
+                left: false,
+                row: 0,
+                column: -1,
+                fingers: vec![0, 0, 1, 2, 3],
+            },
+            Row {
+                left: false,
+                row: 1,
+                column: 0,
+                fingers: vec![THUMB, THUMB],
+            },
+        ]);
+        let keys: Keys = [('q', 0_u8), ('r', 3), ('p', 5), ('y', 8), ('e', 10)]
+            .iter()
+            .cloned()
+            .collect();
+
+        let layout = geometry.format_layout(&keys);
+
+        assert_eq!(layout, "q__r  y__p_  e_");
+        assert_eq!(geometry.parse_layout(&layout), Some(keys));
+        assert_eq!(geometry.mirror(5), Some(0));
+        assert_eq!(geometry.mirror(4), None);
+        assert_eq!(geometry.parse_layout("q__r  y_p_"), None);
+    }
+}
@@ -1,144 +1,354 @@
-use super::{Behaviour, Efforts, FrozenKeys, Position};
-use ed_balance::{CliSettings, Context};
+use super::{
+    get_space, Behaviour, Corpus, Efforts, EffortsConfig, Geometry, KeyboardConfig, Position,
+    ShiftKeys, Thumbs, TrigramWeights, Words, FINGERS_COUNT, MAX_EFFORT, MIN_EFFORT,
+};
+use ed_balance::{BalancePenalty, CliSettings, Context, NormalizationSummary, TextNormalization};
 use itertools::Itertools;
-use serde_json::{self, Value};
 use std::{
     collections::{HashMap, HashSet},
     path::PathBuf,
+    sync::Arc,
 };
 
-pub fn create(settings: &CliSettings) -> Option<Behaviour> {
-    let context = Context::new(settings);
-    let path = settings.keyboard.clone()?;
-    let json = load_json(&path)?;
-    let words = load_words(&settings.text.clone()?)?;
-    let frozen_keys = load_frozen(&json)?;
-    let efforts = load_efforts(&json)?;
-    let switch_penalty = json["switchPenalty"].as_f64()?;
-    let same_key_penalty = json["sameKeyPenalty"].as_f64()?;
-    let blocked_keys: HashSet<Position> = json["blocked"]
-        .as_array()?
-        .into_iter()
-        .map(|x| x.as_u64().unwrap() as Position)
+pub fn create(settings: &CliSettings) -> Result<Behaviour, String> {
+    let config = load_config(settings)?;
+    // upper case letters are typed with the shift.
+    let alphabet: HashSet<char> = ('a'..='z')
+        .chain(config.frozen.keys().copied())
+        .flat_map(|x| x.to_uppercase().chain(Some(x)))
         .collect();
+    let normalization = TextNormalization {
+        lowercase: !settings.keep_case,
+        fold: !settings.keep_accents,
+        punctuation: settings.punctuation,
+        unknown_chars: settings.unknown_chars.clone(),
+    };
+    let text = settings.text.as_ref().ok_or("A sample text is required.")?;
+    let words = load_words(text, &normalization, &alphabet)?;
+
+    build(settings, &config, &words)
+}
+
+/// Checks the keyboard settings without a sample text.
+pub fn validate(settings: &CliSettings) -> Result<Behaviour, String> {
+    let config = load_config(settings)?;
+
+    build(settings, &config, &Words::new())
+}
 
-    Some(Behaviour {
-        context,
-        words,
-        frozen_keys,
+fn load_config(settings: &CliSettings) -> Result<KeyboardConfig, String> {
+    let path = settings
+        .keyboard
+        .as_ref()
+        .ok_or("The keyboard settings are required.")?;
+
+    KeyboardConfig::load(path)
+}
+
+fn build(
+    settings: &CliSettings,
+    config: &KeyboardConfig,
+    words: &Words,
+) -> Result<Behaviour, String> {
+    let geometry = config.validate()?;
+    let efforts = load_efforts(config, &geometry);
+    let thumbs = load_thumbs(config, &geometry);
+    let corpus = match thumbs {
+        Some(_) => Corpus::with_spaces(words),
+        None => Corpus::new(words),
+    };
+    let finger_targets = config.finger_loads.as_ref().map(|x| get_finger_targets(x));
+    let trigram_weights = TrigramWeights {
+        alternation: config.alternation_weight,
+        roll: config.trigram_roll_weight,
+        redirect: config.redirect_weight,
+        one_hand: config.one_hand_weight,
+    };
+    let shift_keys = ShiftKeys {
+        left: config.shift.left,
+        right: config.shift.right,
+        coordination: config.shift.coordination,
+        same_hand_penalty: config.shift.same_hand_penalty,
+    };
+
+    let behaviour = Behaviour {
+        context: Context::new(settings),
+        corpus,
+        fingers: geometry.get_fingers(),
+        frozen_keys: config.get_frozen().collect(),
+        blocked_keys: config.get_blocked(&geometry),
+        geometry: Arc::new(geometry),
         efforts,
-        switch_penalty,
-        same_key_penalty,
-        blocked_keys,
+        switch_penalty: config.switch_penalty,
+        same_key_penalty: config.same_key_penalty,
+        balance_penalty: settings.balance_penalty.unwrap_or(BalancePenalty::Keyboard),
+        finger_targets,
+        finger_penalty: config.finger_penalty,
+        inward_roll_weight: config.inward_roll_weight,
+        outward_roll_weight: config.outward_roll_weight,
+        same_finger_penalty: config.same_finger_penalty,
+        same_finger_cap: config.same_finger_cap,
+        trigram_weights,
+        shift_keys,
+        thumbs,
+        output_format: settings.output_format,
+        hands: settings.split.clone(),
+    };
+
+    validate_efforts(&behaviour).and_then(|_| validate_hands(&behaviour))?;
+
+    Ok(behaviour)
+}
+
+/// The thumb of the space and its effort, the settings are validated.
+fn load_thumbs(config: &KeyboardConfig, geometry: &Geometry) -> Option<Thumbs> {
+    let thumbs = config.thumbs.as_ref()?;
+
+    Some(Thumbs {
+        space: get_space(thumbs.space, geometry)?,
+        effort: normalize_effort(thumbs.effort, get_factor(config.max_effort)),
     })
 }
 
-fn load_words(path: &PathBuf) -> Option<Vec<String>> {
-    let text = std::fs::read_to_string(path).ok()?;
-    let words = text.split(' ').map_into().collect_vec();
-    Some(words)
+/// Each pair of keys of a hand which can have letters must have an effort,
+/// thumbs have their own effort.
+fn validate_efforts(behaviour: &Behaviour) -> Result<(), String> {
+    let geometry = &behaviour.geometry;
+    let positions = geometry
+        .positions()
+        .filter(|x| !behaviour.blocked_keys.contains(x) && !geometry.is_thumb(*x))
+        .collect_vec();
+
+    for (&from, &to) in positions.iter().cartesian_product(positions.iter()) {
+        if geometry.is_left(from) != geometry.is_left(to) {
+            continue;
+        }
+
+        if behaviour
+            .efforts
+            .get(&from)
+            .and_then(|x| x.get(&to))
+            .is_none()
+        {
+            return Err(format!(
+                "There is no effort from the position {} to {}.",
+                from, to
+            ));
+        }
+    }
+
+    Ok(())
 }
 
-fn parse_u8(str: &String) -> Option<Position> {
-    str.parse::<Position>().ok()
+/// Frozen keys must be on their hands and hands must have enough free keys.
+fn validate_hands(behaviour: &Behaviour) -> Result<(), String> {
+    let hands = match &behaviour.hands {
+        Some(hands) => hands,
+        None => return Ok(()),
+    };
+
+    if let Some((letter, _)) = behaviour
+        .frozen_keys
+        .iter()
+        .find(|(&letter, &position)| !behaviour.fits_hand(letter, position))
+    {
+        return Err(format!("'{}' is frozen on the other hand.", letter));
+    }
+
+    let frozen_positions: HashSet<_> = behaviour.frozen_keys.values().collect();
+    for (name, letters, left) in [("left", &hands.left, true), ("right", &hands.right, false)] {
+        let keys = behaviour
+            .geometry
+            .positions()
+            .filter(|&x| behaviour.geometry.is_left(x) == left)
+            .filter(|x| !behaviour.blocked_keys.contains(x) && !frozen_positions.contains(x))
+            .count();
+        let letters = letters
+            .iter()
+            .filter(|x| !behaviour.frozen_keys.contains_key(x))
+            .count();
+
+        if letters > keys {
+            return Err(format!(
+                "The {} hand has {} free keys for {} letters.",
+                name, keys, letters
+            ));
+        }
+    }
+
+    Ok(())
 }
 
-const MIN_VALUE: f64 = 1.;
-const MAX_VALUE: f64 = 5.;
+fn load_words(
+    path: &PathBuf,
+    normalization: &TextNormalization,
+    alphabet: &HashSet<char>,
+) -> Result<Words, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}.", path.display(), e))?;
+    let (words, summary) = parse_words(&text, normalization, alphabet)?;
+
+    if !summary.is_empty() {
+        eprintln!("{}", summary);
+    }
+
+    if words.is_empty() {
+        return Err(format!("{} does not have words.", path.display()));
+    }
+
+    Ok(words)
+}
+
+/// A frequency list or a raw text, each normalized word is counted once.
+fn parse_words(
+    text: &str,
+    normalization: &TextNormalization,
+    alphabet: &HashSet<char>,
+) -> Result<(Words, NormalizationSummary), String> {
+    let parts = parse_frequencies(text)?.unwrap_or_else(|| vec![(text, 1)]);
+    let (words, summary) = normalization.normalize(parts, alphabet)?;
+
+    Ok((collapse_words(words), summary))
+}
+
+/// Lines of `word<TAB>count`, like published frequency lists.\
+/// A text is a list when its first line has a tab, then all lines must be of the list.
+fn parse_frequencies(text: &str) -> Result<Option<Vec<(&str, u64)>>, String> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, x)| !x.trim().is_empty())
+        .peekable();
+
+    match lines.peek() {
+        Some((_, line)) if line.contains('\t') => {}
+        _ => return Ok(None),
+    }
+
+    lines
+        .map(|(i, line)| {
+            line.split_once('\t')
+                .and_then(|(word, count)| Some((word, count.trim().parse::<u64>().ok()?)))
+                .ok_or_else(|| {
+                    format!(
+                        "Line {} of the word frequencies is not a word and a count: {}",
+                        i + 1,
+                        line
+                    )
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
+}
+
+/// Sums counts of the same words, frequent words go first to keep the order of scores stable.
+fn collapse_words(words: impl IntoIterator<Item = (String, u64)>) -> Words {
+    let mut counts: HashMap<String, u64> = HashMap::new();
+    for (word, count) in words {
+        *counts.entry(word).or_default() += count;
+    }
+
+    counts
+        .into_iter()
+        .sorted_by(|(a_word, a_count), (b_word, b_count)| {
+            b_count.cmp(a_count).then_with(|| a_word.cmp(b_word))
+        })
+        .collect()
+}
 
 fn normalize_effort(value: f64, factor: f64) -> f64 {
     debug_assert!(
-        value >= MIN_VALUE,
-        format!("Minimal allowed value is {}", MIN_VALUE)
+        value >= MIN_EFFORT,
+        "Minimal allowed value is {}",
+        MIN_EFFORT
     );
     debug_assert!(
-        value <= 5.,
-        format!("Maximal allowed value is {}", MAX_VALUE)
+        value <= MAX_EFFORT,
+        "Maximal allowed value is {}",
+        MAX_EFFORT
     );
 
     (value - 1.) * factor + 1.
 }
 
-fn parse_nested_efforts(
-    json: &Value,
-    keys_shift: Position,
-    factor: f64,
-) -> Option<HashMap<Position, f64>> {
-    json.as_object()?
+fn parse_efforts(efforts: &EffortsConfig, factor: f64) -> Efforts {
+    efforts
         .iter()
-        .map(|(key, value)| {
-            let key = parse_u8(key)? + keys_shift;
-            let value = normalize_effort(value.as_f64()?, factor);
-            Some((key, value))
-        })
-        .collect()
-}
-
-fn parse_efforts(json: &Value, keys_shift: Position, factor: f64) -> Option<Efforts> {
-    json["efforts"]
-        .as_object()?
-        .iter()
-        .map(|(key, value)| {
-            let key = parse_u8(key)? + keys_shift;
-            let value = parse_nested_efforts(value, keys_shift, factor)?;
-            Some((key, value))
+        .map(|(&from, efforts)| {
+            let efforts = efforts
+                .iter()
+                .map(|(&to, &value)| (to, normalize_effort(value, factor)))
+                .collect();
+            (from, efforts)
         })
         .collect()
 }
 
 fn get_factor(max: f64) -> f64 {
-    (max - 1.) / (MAX_VALUE - 1.)
+    (max - 1.) / (MAX_EFFORT - 1.)
 }
 
-fn load_efforts(json: &Value) -> Option<Efforts> {
-    let max = json["maxEffort"].as_f64()?;
-    let factor = get_factor(max);
-    let mut left = parse_efforts(json, 0, factor)?;
-    // the right part is symmetrical to the left so we can just add 15 to get right efforts.
-    // for a standard keyboard it will be easier to have all efforts in the json file.
-    let right = parse_efforts(json, 15, factor)?;
-    left.extend(right);
-
-    Some(left)
-}
+/// Efforts of the left part and of the right part from `rightEfforts`,
+/// without them the right part mirrors the left one.\
+/// `effortScales` multiply efforts of each hand, like `{ "left": 1, "right": 1.2 }`.
+fn load_efforts(config: &KeyboardConfig, geometry: &Geometry) -> Efforts {
+    let factor = get_factor(config.max_effort);
+    let left = parse_efforts(&config.efforts, factor);
+    let right = match &config.right_efforts {
+        Some(right) => parse_efforts(right, factor),
+        None => mirror_efforts(&left, geometry),
+    };
+    let scales = &config.effort_scales;
 
-fn load_json(keyboard: &PathBuf) -> Option<Value> {
-    let content = std::fs::read_to_string(keyboard).ok()?;
-    serde_json::from_str(&content).ok()
+    scale_efforts(left, scales.left)
+        .chain(scale_efforts(right, scales.right))
+        .collect()
 }
 
-fn load_frozen(json: &Value) -> Option<FrozenKeys> {
-    json["frozen"]
-        .as_object()?
-        .iter()
-        .map(|(key, value)| {
-            let key = key.chars().next()?;
-            let value = parse_u8(&value.as_str()?.to_string())?;
-            Some((key, value))
+/// Keys take efforts of their mirrored keys, keys without a mirrored key do not have efforts.\
+/// For a standard keyboard it will be easier to have all efforts in the json file.
+fn mirror_efforts(left: &Efforts, geometry: &Geometry) -> Efforts {
+    left.iter()
+        .filter_map(|(&from, efforts)| {
+            let efforts = efforts
+                .iter()
+                .filter_map(|(&to, &effort)| Some((geometry.mirror(to)?, effort)))
+                .collect();
+            Some((geometry.mirror(from)?, efforts))
         })
         .collect()
 }
 
+fn scale_efforts(
+    efforts: Efforts,
+    scale: f64,
+) -> impl Iterator<Item = (Position, HashMap<Position, f64>)> {
+    efforts.into_iter().map(move |(from, efforts)| {
+        let efforts = efforts.into_iter().map(|(to, x)| (to, x * scale)).collect();
+        (from, efforts)
+    })
+}
+
+/// Loads of fingers from the left pinky to the right one in any units, like percents.\
+/// They are converted to shares.
+fn get_finger_targets(loads: &[f64]) -> Vec<f64> {
+    debug_assert_eq!(loads.len(), FINGERS_COUNT);
+    let total: f64 = loads.iter().sum();
+
+    loads.iter().map(|x| x / total).collect()
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::behaviour::{config::tests::parse, tests::default_behaviour};
+    use ed_balance::{PunctuationPolicy, UnknownCharsPolicy};
     use serde_json::json;
 
     #[test]
     fn test_load() {
-        let json = json!({
-        "maxEffort": 5,
-        "efforts": {
-            "0": {
-                "0": 1,
-                "1": 2
-            },
-            "1": {
-                "0": 3,
-                "1": 4
-            },
-        }});
-        let actual = load_efforts(&json).unwrap();
+        let config = parse(json!({ "maxEffort": 5 })).unwrap();
+        let actual = load_efforts(&config, &Geometry::default());
         let expected: Efforts = [
             (0, [(0, 1.), (1, 2.)].iter().cloned().collect()),
             (1, [(0, 3.), (1, 4.)].iter().cloned().collect()),
@@ -152,6 +362,164 @@ pub mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn should_load_right_efforts() {
+        let config = parse(json!({
+        "maxEffort": 5,
+        "rightEfforts": { "15": { "15": 2 }, "20": { "20": 5 } },
+        "effortScales": { "right": 1.5 },
+        }))
+        .unwrap();
+
+        let actual = load_efforts(&config, &Geometry::default());
+
+        assert_eq!(actual[&1][&0], 3.);
+        assert_eq!(actual[&15][&15], 3.);
+        assert_eq!(actual[&20][&20], 7.5);
+        assert!(!actual.contains_key(&16));
+    }
+
+    #[test]
+    fn should_mirror_efforts_by_columns() {
+        let config = parse(json!({
+        "maxEffort": 5,
+        "blocked": [],
+        "geometry": [
+            { "hand": "left", "row": 0, "fingers": [0, 1, 2, 3, 3] },
+            { "hand": "right", "row": 0, "column": -1, "fingers": [0, 0, 1, 2, 3, 3] },
+        ],
+        }))
+        .unwrap();
+
+        let efforts = load_efforts(&config, &config.validate().unwrap());
+
+        // the outer key of the right part does not have a mirrored key.
+        assert_eq!(efforts[&6][&7], 2.);
+        assert_eq!(efforts[&7][&6], 3.);
+        assert!(!efforts.contains_key(&5));
+    }
+
+    #[test]
+    fn should_validate_efforts() {
+        let mut behaviour = default_behaviour();
+        let positions = [0_u8, 1, 15];
+        behaviour.blocked_keys = behaviour
+            .geometry
+            .positions()
+            .filter(|x| !positions.contains(x))
+            .collect();
+        behaviour.efforts = positions
+            .iter()
+            .map(|&x| (x, positions.iter().map(|&y| (y, 1.)).collect()))
+            .collect();
+
+        assert_eq!(validate_efforts(&behaviour), Ok(()));
+
+        behaviour.efforts.get_mut(&1).unwrap().remove(&0);
+        assert_eq!(
+            validate_efforts(&behaviour),
+            Err("There is no effort from the position 1 to 0.".to_string())
+        );
+    }
+
+    #[test]
+    fn should_load_thumbs() {
+        let geometry = Geometry::default();
+        let config = parse(json!({
+            "maxEffort": 3,
+            "thumbs": { "space": "left", "effort": 3, "letters": [31] },
+        }))
+        .unwrap();
+        let thumbs = load_thumbs(&config, &geometry).unwrap();
+
+        assert_eq!(thumbs.space, [30, 30]);
+        assert_eq!(thumbs.effort, 2.);
+
+        let config = parse(json!({ "thumbs": { "space": "either" } })).unwrap();
+        assert_eq!(load_thumbs(&config, &geometry).unwrap().space, [31, 30]);
+
+        let config = parse(json!({ "thumbs": { "space": "left", "letters": [29] } })).unwrap();
+        assert_eq!(
+            config.validate().err(),
+            Some("The thumb letters position 29 is not a thumb.".to_string())
+        );
+        assert!(parse(json!({ "thumbs": { "space": "up" } })).is_err());
+    }
+
+    #[test]
+    fn should_load_finger_targets() {
+        let actual = get_finger_targets(&[1., 1., 2., 4., 4., 2., 1., 1.]);
+
+        assert_eq!(actual[0], 1. / 16.);
+        assert_eq!(actual[3], 0.25);
+
+        let config = parse(json!({ "fingerLoads": [0, 0, 0, 0, 0, 0, 0, 0] })).unwrap();
+        assert_eq!(
+            config.validate().err(),
+            Some("fingerLoads must be positive.".to_string())
+        );
+        let config = parse(json!({ "fingerLoads": [1, 1] })).unwrap();
+        assert_eq!(
+            config.validate().err(),
+            Some("fingerLoads has 2 values, it must have 8.".to_string())
+        );
+    }
+
+    fn default_normalization() -> TextNormalization {
+        TextNormalization {
+            lowercase: true,
+            fold: true,
+            punctuation: PunctuationPolicy::Split,
+            unknown_chars: UnknownCharsPolicy::Skip,
+        }
+    }
+
+    #[test]
+    fn should_count_words_of_text() {
+        let alphabet = ('a'..='z').collect();
+
+        let (actual, summary) = parse_words(
+            "The cat and the dog,  and the\nend 42",
+            &default_normalization(),
+            &alphabet,
+        )
+        .unwrap();
+
+        assert_eq!(
+            actual,
+            vec![
+                ("the".to_string(), 3),
+                ("and".to_string(), 2),
+                ("cat".to_string(), 1),
+                ("dog".to_string(), 1),
+                ("end".to_string(), 1),
+            ]
+        );
+        assert_eq!(summary.dropped_count, 1);
+    }
+
+    #[test]
+    fn should_parse_frequencies() {
+        let alphabet = ('a'..='z').collect();
+
+        let (actual, _) = parse_words(
+            "the\t23135851162\nof\t40\n\nThe\t5\n",
+            &default_normalization(),
+            &alphabet,
+        )
+        .unwrap();
+
+        assert_eq!(
+            actual,
+            vec![("the".to_string(), 23135851167), ("of".to_string(), 40)]
+        );
+        assert_eq!(parse_frequencies("the cat\nof\t40"), Ok(None));
+        assert_eq!(
+            parse_frequencies("the\t23135851162\nof 40"),
+            Err("Line 2 of the word frequencies is not a word and a count: of 40".to_string())
+        );
+    }
+
     #[test]
     fn test_normalize_effort_for_1() {
         let factor = get_factor(3.);
@@ -1,25 +1,50 @@
+mod config;
+mod corpus;
 mod generator;
+mod geometry;
 mod loader;
 mod model;
 mod mutator;
 mod recombination;
 mod score_calculator;
 
-use ed_balance::{get_version, CliSettings, Context, IBehaviour, IIndividual};
-use io::BufRead;
+pub use config::*;
+pub use corpus::Corpus;
+use ed_balance::{format_records, get_version, parse_records, CliSettings, Context, IBehaviour};
+pub use geometry::*;
 use itertools::Itertools;
 pub use model::*;
 use std::{
     cmp::Ordering,
-    fs::File,
+    fs::{self, File},
     io::{self, Write},
 };
 
-use crate::keyboard::{Keyboard, Keys, Mutation};
+use crate::{
+    keyboard::{Keyboard, Keys, Mutation},
+    record::KeyboardRecord,
+};
+
+/// Checks the keyboard settings without a sample text, a summary of them when they are valid.
+pub fn validate(settings: &CliSettings) -> Result<String, String> {
+    let behaviour = loader::validate(settings)?;
+    let geometry = &behaviour.geometry;
+    let free = geometry
+        .positions()
+        .filter(|x| !behaviour.blocked_keys.contains(x))
+        .count();
+
+    Ok(format!(
+        "The keyboard settings are valid: {} keys, {} free keys, {} frozen letters.",
+        geometry.positions().count(),
+        free,
+        behaviour.frozen_keys.len()
+    ))
+}
 
 impl IBehaviour<Mutation, Keyboard> for Behaviour {
     fn new(settings: &CliSettings) -> Self {
-        loader::create(settings).expect("Failed to create the behaviour object.")
+        loader::create(settings).unwrap_or_else(|e| panic!("{}", e))
     }
 
     fn generate(&self) -> Box<Keyboard> {
@@ -27,8 +52,7 @@ impl IBehaviour<Mutation, Keyboard> for Behaviour {
     }
 
     fn calculate_score(&self, individual: &Keyboard) -> f64 {
-        let (effort, _, _, _, _, _) = score_calculator::calculate_score(&self, &individual.keys);
-        effort
+        score_calculator::calculate_score(self, &individual.keys).effort
     }
 
     fn cross(&self, individual: &Keyboard, partner: &Keyboard) -> Box<Keyboard> {
@@ -39,14 +63,11 @@ impl IBehaviour<Mutation, Keyboard> for Behaviour {
         mutator::mutate(self, individual)
     }
 
-    fn get_context<'a>(&'a self) -> &'a Context {
+    fn get_context(&self) -> &Context {
         &self.context
     }
 
     fn score_cmp(&self, a: &Keyboard, b: &Keyboard) -> Ordering {
-        let (a_total, _, _, _, _, _) = a.score;
-        let (b_total, _, _, _, _, _) = b.score;
-
         fn get_sorted_position(keys: &Keys) -> Vec<&Position> {
             keys.iter()
                 .sorted_by(|(c1, _), (c2, _)| c1.cmp(c2))
@@ -54,21 +75,26 @@ impl IBehaviour<Mutation, Keyboard> for Behaviour {
                 .collect_vec()
         }
 
-        a_total
-            .partial_cmp(&b_total)
+        // the cap of same finger bigrams goes before the effort.
+        self.get_same_finger_excess(&a.score)
+            .partial_cmp(&self.get_same_finger_excess(&b.score))
             .unwrap()
+            .then_with(|| a.score.effort.partial_cmp(&b.score.effort).unwrap())
             .then_with(|| get_sorted_position(&a.keys).cmp(&get_sorted_position(&b.keys)))
     }
 
     fn load(&self) -> std::io::Result<Vec<Box<Keyboard>>> {
-        if let Ok(file) = File::open("data/keyboards.csv") {
-            let lines = io::BufReader::new(file).lines();
-
-            let keyboards = lines
-                .map(|x| {
-                    let line = x.unwrap();
-                    let keys = line_to_keys(&line);
-                    let score = score_calculator::calculate_score(&self, &keys);
+        if let Ok(text) = fs::read_to_string(self.get_path()) {
+            let records = parse_records(&text, KeyboardRecord::from_legacy)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+            // saved keyboards of other splits do not fit the hand assignment.
+            let keyboards = records
+                .iter()
+                .filter_map(|record| self.geometry.parse_layout(&record.layout))
+                .filter(|keys| keys.iter().all(|(&c, &p)| self.fits_hand(c, p)))
+                .map(|keys| {
+                    let score = score_calculator::calculate_score(self, &keys);
                     let version = get_version();
 
                     Keyboard::new(
@@ -78,6 +104,7 @@ impl IBehaviour<Mutation, Keyboard> for Behaviour {
                         Vec::new(),
                         version,
                         keys,
+                        self.geometry.clone(),
                     )
                 })
                 .collect_vec();
@@ -88,9 +115,11 @@ impl IBehaviour<Mutation, Keyboard> for Behaviour {
         Ok(Vec::new())
     }
 
-    fn save(individuals: &Vec<Box<Keyboard>>) -> std::io::Result<()> {
-        let text = individuals.iter().map(|x| x.to_string()).join("\n");
-        let mut file = File::create("data/keyboards.csv")?;
+    fn save(&self, individuals: &[Box<Keyboard>]) -> std::io::Result<()> {
+        let records = individuals.iter().map(|x| x.to_record()).collect_vec();
+        let text = format_records(&records, self.output_format)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        let mut file = File::create(self.get_path())?;
         file.write_all(text.as_bytes())?;
         file.sync_all()?;
 
@@ -98,32 +127,23 @@ impl IBehaviour<Mutation, Keyboard> for Behaviour {
     }
 }
 
-fn line_to_keys(line: &str) -> Keys {
-    let parts = line.split(';').collect_vec();
-    let line = parts[0];
-    let parts = line.split_whitespace().collect_vec();
-    let left = parts
-        .iter()
-        .take(3)
-        .flat_map(|part| part.chars())
-        .enumerate()
-        .map(|(p, c)| (c, p as u8));
-
-    parts
-        .iter()
-        .skip(3)
-        .flat_map(|part| part.chars().rev())
-        .enumerate()
-        .map(|(p, c)| (c, p as u8 + 15_u8))
-        .merge(left)
-        .filter(|(c, _)| c != &'_')
-        .collect()
+impl Behaviour {
+    /// Results of the previous run, `load` starts from them and `save` overwrites them.\
+    /// For the legacy format it is the tracked `data/keyboards.csv`.
+    fn get_path(&self) -> String {
+        format!("data/keyboards.{}", self.output_format.extension())
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use std::collections::{HashMap, HashSet};
+    use crate::keyboard::Score;
+    use ed_balance::{BalancePenalty, OutputFormat};
+    use std::{
+        collections::{HashMap, HashSet},
+        sync::Arc,
+    };
 
     fn default_context() -> Context {
         Context {
@@ -150,21 +170,37 @@ pub mod tests {
         efforts
     }
 
-    fn default_behaviour() -> Behaviour {
+    pub fn default_behaviour() -> Behaviour {
         Behaviour {
             context: default_context(),
+            geometry: Arc::new(Geometry::default()),
             blocked_keys: HashSet::new(),
             efforts: default_efforts(),
             frozen_keys: [('a', 1_u8)].iter().cloned().collect(),
             same_key_penalty: 2.,
             switch_penalty: 3.,
-            words: ["abc".to_string()].to_vec(),
+            balance_penalty: BalancePenalty::Keyboard,
+            fingers: (0..30).map(|x| (x, x % 8)).collect(),
+            finger_targets: None,
+            finger_penalty: 0.,
+            inward_roll_weight: 1.,
+            outward_roll_weight: 1.,
+            same_finger_penalty: 1.,
+            same_finger_cap: None,
+            trigram_weights: TrigramWeights::default(),
+            shift_keys: ShiftKeys::default(),
+            thumbs: None,
+            output_format: OutputFormat::Legacy,
+            hands: None,
+            corpus: Corpus::new(&vec![("abc".to_string(), 1)]),
         }
     }
 
     #[test]
     fn test_line_to_keys() {
-        let actual = line_to_keys("hntio asler zxcvd  wyfj_ qubpg km___; 5625.250; some text");
+        let actual = Geometry::default()
+            .parse_layout("hntio asler zxcvd  wyfj_ qubpg km___; 5625.250; some text")
+            .unwrap();
         let expected: Keys = [
             ('h', 0_u8),
             ('n', 1_u8),
@@ -200,9 +236,143 @@ pub mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn should_keep_letters_on_their_hands() {
+        let positions = [0_u8, 1, 2, 15, 16, 17];
+        let mut behaviour = default_behaviour();
+        behaviour.context.mutations_count = 3;
+        behaviour.frozen_keys = HashMap::new();
+        behaviour.efforts = positions
+            .iter()
+            .map(|&x| (x, positions.iter().map(|&y| (y, 1.)).collect()))
+            .collect();
+        behaviour.hands = Some("ab de".parse().unwrap());
+        let keys: Keys = [
+            ('a', 0_u8),
+            ('b', 1_u8),
+            ('c', 2_u8),
+            ('d', 15_u8),
+            ('e', 16_u8),
+            ('f', 17_u8),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        let parent = Keyboard::new(
+            "version".to_string(),
+            keys.clone(),
+            score_calculator::calculate_score(&behaviour, &keys),
+            Vec::new(),
+            "version".to_string(),
+            keys,
+            behaviour.geometry.clone(),
+        );
+
+        for _ in 0..50 {
+            let child = mutator::mutate(&behaviour, &parent);
+            let partner = mutator::mutate(&behaviour, &parent);
+            let crossed = behaviour.cross(&child, &partner);
+
+            for keyboard in [&child, &crossed] {
+                let is_left = |x: char| behaviour.geometry.is_left(keyboard.keys[&x]);
+                assert!(is_left('a') && is_left('b'));
+                assert!(!is_left('d') && !is_left('e'));
+                assert_eq!(keyboard.keys.values().unique().count(), 6);
+            }
+        }
+    }
+
+    #[test]
+    fn should_detect_rolls() {
+        let mut behaviour = default_behaviour();
+        behaviour.fingers = [(0, 0), (5, 0), (6, 1), (8, 3), (9, 3), (20, 7), (21, 6)]
+            .iter()
+            .cloned()
+            .collect();
+
+        assert_eq!(behaviour.get_roll(5, 6), Some(Roll::Inward));
+        assert_eq!(behaviour.get_roll(8, 6), Some(Roll::Outward));
+        assert_eq!(behaviour.get_roll(20, 21), Some(Roll::Inward));
+        assert_eq!(behaviour.get_roll(8, 9), None);
+        assert_eq!(behaviour.get_roll(0, 6), None);
+        assert_eq!(behaviour.get_roll(6, 21), None);
+    }
+
+    #[test]
+    fn should_put_same_finger_cap_before_effort() {
+        let mut behaviour = default_behaviour();
+        behaviour.same_finger_cap = Some(0.1);
+        let keyboard = |effort: f64, same_finger: u64| Keyboard {
+            keys: HashMap::new(),
+            mutations: Vec::new(),
+            parent: HashMap::new(),
+            parent_version: "parent_version".to_string(),
+            score: Score {
+                effort,
+                left_counter: 10,
+                same_finger_bigrams: vec![same_finger],
+                ..Score::default()
+            },
+            version: "version".to_string(),
+            geometry: behaviour.geometry.clone(),
+        };
+
+        assert_eq!(
+            behaviour.score_cmp(&keyboard(2., 1), &keyboard(1., 2)),
+            Ordering::Less
+        );
+        assert_eq!(
+            behaviour.score_cmp(&keyboard(1., 1), &keyboard(2., 0)),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn should_classify_trigrams() {
+        let mut behaviour = default_behaviour();
+        behaviour.fingers = [(5, 0), (6, 1), (8, 3), (20, 7)].iter().cloned().collect();
+
+        assert_eq!(behaviour.get_trigram(5, 20, 6), Trigram::Alternation);
+        assert_eq!(behaviour.get_trigram(5, 6, 20), Trigram::Roll);
+        assert_eq!(behaviour.get_trigram(20, 5, 6), Trigram::Roll);
+        assert_eq!(behaviour.get_trigram(5, 8, 6), Trigram::Redirect);
+        assert_eq!(behaviour.get_trigram(5, 6, 8), Trigram::OneHand);
+    }
+
+    #[test]
+    fn should_keep_letters_on_thumbs_in_layouts() {
+        let keys: Keys = [
+            ('a', 0_u8),
+            ('c', 5_u8),
+            ('d', 10_u8),
+            ('b', 16_u8),
+            ('f', 20_u8),
+            ('g', 25_u8),
+            ('e', 31_u8),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        let keyboard = Keyboard::new(
+            "version".to_string(),
+            keys.clone(),
+            Score::default(),
+            Vec::new(),
+            "version".to_string(),
+            keys.clone(),
+            Arc::new(Geometry::default()),
+        );
+
+        let layout = keyboard.to_record().layout;
+
+        assert_eq!(layout, "a____ c____ d____  ___b_ ____f ____g  _e");
+        assert_eq!(keyboard.geometry.parse_layout(&layout), Some(keys));
+    }
+
     #[test]
     fn should_mutate() {
-        let behaviour = default_behaviour();
+        let mut behaviour = default_behaviour();
+        behaviour.context.mutations_count = 1;
         let individual = Keyboard {
             keys: [('a', 0_u8), ('b', 1_u8), ('c', 2_u8)]
                 .iter()
@@ -211,8 +381,9 @@ pub mod tests {
             mutations: Vec::new(),
             parent: HashMap::new(),
             parent_version: "parent_version".to_string(),
-            score: (0., 0, 0, 0, 0., 0.),
+            score: Score::default(),
             version: "version".to_string(),
+            geometry: behaviour.geometry.clone(),
         };
 
         let actual = mutator::mutate(&behaviour, &individual);
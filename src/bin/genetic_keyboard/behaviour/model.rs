@@ -1,11 +1,29 @@
-use ed_balance::Context;
-use std::collections::{HashMap, HashSet};
+use super::{Corpus, Geometry, THUMB};
+use crate::keyboard::Score;
+use ed_balance::{BalancePenalty, Context, OutputFormat, Split};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 pub type Position = u8;
 
+/// Fingers from the left pinky `0` to the right pinky `7`.
+pub type Finger = u8;
+
+pub const FINGERS_COUNT: usize = 8;
+pub const PINKIES: [Finger; 2] = [0, 7];
+
+/// Words of a text are separated by it.
+pub const SPACE: char = ' ';
+
 pub struct Behaviour {
     pub context: Context,
-    pub words: Vec<String>,
+    pub corpus: Corpus,
+
+    /// Keys of the keyboard, keyboards share it to show their layouts.
+    pub geometry: Arc<Geometry>,
 
     /// char * position
     pub frozen_keys: FrozenKeys,
@@ -15,8 +33,271 @@ pub struct Behaviour {
     pub efforts: Efforts,
     pub switch_penalty: f64,
     pub same_key_penalty: f64,
+    pub balance_penalty: BalancePenalty,
+
+    /// A finger of each position.
+    pub fingers: Fingers,
+
+    /// Desired load shares of fingers, they sum up to 1.
+    pub finger_targets: Option<Vec<f64>>,
+
+    /// How much the deviation from finger targets increases the effort.
+    pub finger_penalty: f64,
+
+    /// Multipliers of efforts of rolls, less than 1 makes them preferable.
+    pub inward_roll_weight: f64,
+    pub outward_roll_weight: f64,
+
+    /// A multiplier of efforts of different keys typed by the same finger.
+    pub same_finger_penalty: f64,
+
+    /// The maximal share of same finger bigrams among all bigrams,
+    /// keyboards above it lose to all keyboards below it.
+    pub same_finger_cap: Option<f64>,
+
+    /// Efforts added for each trigram of a class, negative ones make the class preferable.
+    pub trigram_weights: TrigramWeights,
+    pub shift_keys: ShiftKeys,
+
+    /// The space and letters on thumbs, `None` scores words apart without the space.
+    pub thumbs: Option<Thumbs>,
+
+    /// The format of saved keyboards.
+    pub output_format: OutputFormat,
+
+    /// Letters that must be typed by the left or the right hand.
+    pub hands: Option<Split>,
+}
+
+impl Behaviour {
+    /// Whether the hand assignment allows the letter on the position.
+    pub fn fits_hand(&self, letter: char, position: Position) -> bool {
+        match get_hand(self.hands.as_ref(), letter) {
+            Some(left) => left == self.geometry.is_left(position),
+            None => true,
+        }
+    }
+}
+
+/// Consecutive keys of the same hand and row typed by different fingers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Roll {
+    /// From the pinky to the index finger.
+    Inward,
+
+    /// From the index finger to the pinky.
+    Outward,
+}
+
+/// Classes of three consecutive keys.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trigram {
+    /// Hands switch on each key.
+    Alternation,
+
+    /// Two keys of one hand and one key of the other hand.
+    Roll,
+
+    /// Three keys of one hand changing the direction, like `ring - index - middle`.
+    Redirect,
+
+    /// Three keys of one hand in one direction.
+    OneHand,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrigramWeights {
+    pub alternation: f64,
+    pub roll: f64,
+    pub redirect: f64,
+    pub one_hand: f64,
+}
+
+impl TrigramWeights {
+    pub fn get(&self, trigram: Trigram) -> f64 {
+        match trigram {
+            Trigram::Alternation => self.alternation,
+            Trigram::Roll => self.roll,
+            Trigram::Redirect => self.redirect,
+            Trigram::OneHand => self.one_hand,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thumbs {
+    /// Keys of the space next to letters of the left and the right hands,
+    /// they are the same key when one thumb presses the space.
+    pub space: [Position; 2],
+
+    /// The effort of a thumb key.
+    pub effort: f64,
+}
+
+/// Shift keys are outside of letter positions, so only their efforts are known.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShiftKeys {
+    /// Efforts of shift keys of hands, `None` when a hand does not have it.
+    pub left: Option<f64>,
+    pub right: Option<f64>,
+
+    /// An effort added for each upper case letter to press keys together.
+    pub coordination: f64,
+
+    /// A multiplier of the coordination when the shift and the letter are on the same hand.
+    pub same_hand_penalty: f64,
+}
+
+impl ShiftKeys {
+    /// The hand of the shift for a letter and the effort of the shift,
+    /// the shift of the other hand is preferred.
+    pub fn get_shift(&self, letter_is_left: bool) -> Option<(bool, f64)> {
+        let (other, same) = if letter_is_left {
+            (self.right, self.left)
+        } else {
+            (self.left, self.right)
+        };
+
+        match (other, same) {
+            (Some(effort), _) => Some((!letter_is_left, effort + self.coordination)),
+            (None, Some(effort)) => Some((
+                letter_is_left,
+                effort + self.coordination * self.same_hand_penalty,
+            )),
+            (None, None) => None,
+        }
+    }
+}
+
+impl Behaviour {
+    pub fn get_trigram(&self, first: Position, second: Position, third: Position) -> Trigram {
+        let first_is_left = self.geometry.is_left(first);
+        let second_is_left = self.geometry.is_left(second);
+        let third_is_left = self.geometry.is_left(third);
+
+        if first_is_left != second_is_left && second_is_left != third_is_left {
+            return Trigram::Alternation;
+        }
+
+        if first_is_left != second_is_left || second_is_left != third_is_left {
+            return Trigram::Roll;
+        }
+
+        let finger = |x: Position| self.get_hand_finger(x);
+        let first_direction = finger(first).cmp(&finger(second));
+        let second_direction = finger(second).cmp(&finger(third));
+
+        if first_direction != second_direction
+            && first_direction != Ordering::Equal
+            && second_direction != Ordering::Equal
+        {
+            Trigram::Redirect
+        } else {
+            Trigram::OneHand
+        }
+    }
+
+    pub fn is_same_finger(&self, from: Position, to: Position) -> bool {
+        from != to
+            && !self.geometry.is_thumb(from)
+            && !self.geometry.is_thumb(to)
+            && self.fingers[&from] == self.fingers[&to]
+    }
+
+    /// Thumbs move apart from other fingers,
+    /// so a pair with a thumb costs only the press of the second key.
+    pub fn get_effort(&self, from: Position, to: Position) -> f64 {
+        if self.geometry.is_thumb(to) {
+            self.thumbs.as_ref().map_or(1., |x| x.effort)
+        } else if self.geometry.is_thumb(from) {
+            self.efforts[&to][&to]
+        } else {
+            self.efforts[&from][&to]
+        }
+    }
+
+    /// The thumb which presses the space next to the key of a letter.
+    pub fn get_space_key(&self, neighbour: Position) -> Position {
+        let space = self.thumbs.as_ref().expect("The space needs thumbs.").space;
+
+        if self.geometry.is_left(neighbour) {
+            space[0]
+        } else {
+            space[1]
+        }
+    }
+
+    /// Fingers of both hands from the pinky `0` to the thumb `4`.
+    fn get_hand_finger(&self, position: Position) -> Finger {
+        if self.geometry.is_thumb(position) {
+            THUMB
+        } else {
+            get_hand_finger(self.fingers[&position])
+        }
+    }
+
+    /// How much the share of same finger bigrams exceeds the cap, `0` when it fits it.
+    pub fn get_same_finger_excess(&self, score: &Score) -> f64 {
+        let cap = match self.same_finger_cap {
+            Some(cap) => cap,
+            None => return 0.,
+        };
+        let bigrams = score.left_counter + score.right_counter + score.switch_counter;
+
+        if bigrams == 0 {
+            return 0.;
+        }
+
+        let same_finger: u64 = score.same_finger_bigrams.iter().sum();
+
+        (same_finger as f64 / bigrams as f64 - cap).max(0.)
+    }
+
+    pub fn get_roll(&self, from: Position, to: Position) -> Option<Roll> {
+        if self.geometry.is_left(from) != self.geometry.is_left(to)
+            || self.geometry.get_row(from) != self.geometry.get_row(to)
+        {
+            return None;
+        }
+
+        let from = self.get_hand_finger(from);
+        let to = self.get_hand_finger(to);
+
+        match from.cmp(&to) {
+            Ordering::Less => Some(Roll::Inward),
+            Ordering::Greater => Some(Roll::Outward),
+            Ordering::Equal => None,
+        }
+    }
+}
+
+/// Fingers of both hands from the pinky `0` to the index finger `3`.
+fn get_hand_finger(finger: Finger) -> Finger {
+    if finger < 4 {
+        finger
+    } else {
+        FINGERS_COUNT as Finger - 1 - finger
+    }
+}
+
+/// `Some(true)` for letters of the left hand, `None` for letters without a hand.
+pub fn get_hand(hands: Option<&Split>, letter: char) -> Option<bool> {
+    let hands = hands?;
+
+    if hands.left.contains(&letter) {
+        Some(true)
+    } else if hands.right.contains(&letter) {
+        Some(false)
+    } else {
+        None
+    }
 }
 
 pub type Efforts = HashMap<Position, HashMap<Position, f64>>;
 
 pub type FrozenKeys = HashMap<char, Position>;
+
+pub type Fingers = HashMap<Position, Finger>;
+
+/// Unique words with their counts in a text.
+pub type Words = Vec<(String, u64)>;
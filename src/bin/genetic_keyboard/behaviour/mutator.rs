@@ -16,21 +16,36 @@ pub fn mutate(this: &Behaviour, individual: &Keyboard) -> Box<Keyboard> {
 
     keys.shuffle(&mut rng);
     let mutations_count = 1 + (rng.next_u32() as usize % this.context.mutations_count);
+    let mut moved = Vec::with_capacity(mutations_count * 2);
 
-    for index in 0..mutations_count {
-        let second_index = keys.len() - index - 1;
-        let (first_char, first) = keys[index];
-        let (second_char, second) = keys[second_index];
+    // each key moves once, letters with a hand move only inside it.
+    while mutations.len() < mutations_count {
+        let (first_char, first) = match keys.pop() {
+            Some(key) => key,
+            None => break,
+        };
+        let partner = keys.iter().position(|&(second_char, second)| {
+            this.fits_hand(first_char, second) && this.fits_hand(second_char, first)
+        });
 
-        mutations.push(Mutation { first, second });
-        keys[index] = (first_char, second);
-        keys[second_index] = (second_char, first);
+        match partner {
+            Some(index) => {
+                let (second_char, second) = keys.swap_remove(index);
+                mutations.push(Mutation { first, second });
+                moved.push((first_char, second));
+                moved.push((second_char, first));
+            }
+            None => moved.push((first_char, first)),
+        }
     }
 
     let version = get_version();
-    let keys: Keys = keys.into_iter().merge(this.frozen_keys.clone()).collect();
+    let keys: Keys = keys
+        .into_iter()
+        .chain(moved)
+        .merge(this.frozen_keys.clone())
+        .collect();
     debug_assert_eq!(keys.len(), individual.keys.len());
-    debug_assert_eq!(keys.values().max().unwrap(), &29_u8);
 
     let score = calculate_score(this, &keys);
 
@@ -41,5 +56,6 @@ pub fn mutate(this: &Behaviour, individual: &Keyboard) -> Box<Keyboard> {
         mutations,
         individual.version.clone(),
         individual.keys.clone(),
+        this.geometry.clone(),
     )
 }
@@ -9,7 +9,7 @@ pub fn cross(this: &Behaviour, individual: &Keyboard, partner: &Keyboard) -> Box
     let mut keys: HashMap<_, _> = individual
         .parent
         .iter()
-        .map(|(key, value)| (value, key))
+        .map(|(&key, &value)| (value, key))
         .collect();
 
     let mut mutations: Vec<_> = individual
@@ -17,34 +17,43 @@ pub fn cross(this: &Behaviour, individual: &Keyboard, partner: &Keyboard) -> Box
         .iter()
         .chain(partner.mutations.iter())
         .unique()
-        .map(|&x| x)
+        .copied()
         .collect();
 
     mutations.shuffle(&mut rand::thread_rng());
 
-    for mutation in mutations.iter().take(this.context.mutations_count) {
+    // mutations of the partner can move letters of the parent to another hand,
+    // such ones are skipped.
+    let mut applied = Vec::with_capacity(this.context.mutations_count);
+    for mutation in mutations {
+        if applied.len() == this.context.mutations_count {
+            break;
+        }
+
         let first_char = keys[&mutation.first];
         let second_char = keys[&mutation.second];
-        *keys.entry(&mutation.first).or_insert(second_char) = second_char;
-        *keys.entry(&mutation.second).or_insert(first_char) = first_char;
+        if !this.fits_hand(first_char, mutation.second)
+            || !this.fits_hand(second_char, mutation.first)
+        {
+            continue;
+        }
+
+        keys.insert(mutation.first, second_char);
+        keys.insert(mutation.second, first_char);
+        applied.push(mutation);
     }
 
-    let keys: Keys = keys
-        .into_iter()
-        .map(|(&key, &value)| (value, key))
-        .collect();
+    let keys: Keys = keys.into_iter().map(|(key, value)| (value, key)).collect();
 
     let score = calculate_score(this, &keys);
 
-    debug_assert_eq!(keys.len(), 26);
-    debug_assert_eq!(keys.values().max().unwrap(), &29_u8);
-
     Keyboard::new(
         get_version(),
         keys,
         score,
-        mutations,
+        applied,
         individual.parent_version.clone(),
         individual.parent.clone(),
+        this.geometry.clone(),
     )
 }
@@ -1,140 +1,819 @@
-use super::{Behaviour, Position};
-use crate::keyboard::{get_factor, Keys, Score};
+use super::{Behaviour, Position, Roll, Trigram, FINGERS_COUNT, PINKIES, SPACE};
+use crate::keyboard::{Keys, Score};
 use itertools::Itertools;
-use std::collections::HashMap;
+
+/// Metrics of a part of a text, they are summed up into the `Score`.
+#[derive(Debug, Default, PartialEq)]
+struct PartialScore {
+    effort: f64,
+    left_counter: u64,
+    right_counter: u64,
+    switch_counter: u64,
+    alternations: u64,
+    trigram_rolls: u64,
+    redirects: u64,
+    one_hand_runs: u64,
+    shift_counter: u64,
+    left_effort: f64,
+    right_effort: f64,
+    inward_rolls: u64,
+    outward_rolls: u64,
+    same_finger_bigrams: [u64; FINGERS_COUNT],
+}
+
+impl PartialScore {
+    fn add(mut self, other: PartialScore) -> PartialScore {
+        self.effort += other.effort;
+        self.left_counter += other.left_counter;
+        self.right_counter += other.right_counter;
+        self.switch_counter += other.switch_counter;
+        self.alternations += other.alternations;
+        self.trigram_rolls += other.trigram_rolls;
+        self.redirects += other.redirects;
+        self.one_hand_runs += other.one_hand_runs;
+        self.shift_counter += other.shift_counter;
+        self.left_effort += other.left_effort;
+        self.right_effort += other.right_effort;
+        self.inward_rolls += other.inward_rolls;
+        self.outward_rolls += other.outward_rolls;
+        for (total, count) in self
+            .same_finger_bigrams
+            .iter_mut()
+            .zip(other.same_finger_bigrams)
+        {
+            *total += count;
+        }
+
+        self
+    }
+
+    /// Metrics of all occurrences of a part.
+    fn scale(mut self, count: u64) -> PartialScore {
+        let factor = count as f64;
+
+        self.effort *= factor;
+        self.left_counter *= count;
+        self.right_counter *= count;
+        self.switch_counter *= count;
+        self.alternations *= count;
+        self.trigram_rolls *= count;
+        self.redirects *= count;
+        self.one_hand_runs *= count;
+        self.shift_counter *= count;
+        self.left_effort *= factor;
+        self.right_effort *= factor;
+        self.inward_rolls *= count;
+        self.outward_rolls *= count;
+        self.same_finger_bigrams
+            .iter_mut()
+            .for_each(|x| *x *= count);
+
+        self
+    }
+}
 
 /// lower score better because it shows less efforts and better ballance.
 pub fn calculate_score(this: &Behaviour, keyboard: &Keys) -> Score {
-    let (effort, left_counter, right_counter, switch, left_effort, right_effort) = this
-        .words
-        .iter()
-        .map(|x| calculate_word_score(this, keyboard, x))
-        .fold(
-            (0., 0, 0, 0, 0., 0.),
-            |(
-                effort_total,
-                left_total,
-                right_total,
-                switch_total,
-                left_effort_total,
-                right_effort_total,
-            ),
-             (
-                word_effort,
-                word_left,
-                word_right,
-                word_switch,
-                word_left_effort,
-                word_right_effort,
-            )| {
-                (
-                    effort_total + word_effort,
-                    left_total + word_left,
-                    right_total + word_right,
-                    switch_total + word_switch,
-                    left_effort_total + word_left_effort,
-                    right_effort_total + word_right_effort,
-                )
-            },
-        );
+    let PartialScore {
+        effort,
+        left_counter,
+        right_counter,
+        switch_counter,
+        alternations,
+        trigram_rolls,
+        redirects,
+        one_hand_runs,
+        shift_counter,
+        left_effort,
+        right_effort,
+        inward_rolls,
+        outward_rolls,
+        same_finger_bigrams,
+    } = calculate_partial_score(this, keyboard);
 
-    let factor = get_factor(left_effort, right_effort);
-    let effort = effort * factor;
+    let factor = this
+        .balance_penalty
+        .effort_factor(left_effort, right_effort);
+    let finger_loads = calculate_finger_loads(this, keyboard);
+    let (finger_deviation, pinky_overload) = match &this.finger_targets {
+        Some(targets) => calculate_finger_deviation(&finger_loads, targets),
+        None => (0., 0.),
+    };
+    let effort = effort * factor * (1. + this.finger_penalty * finger_deviation);
 
-    (
+    Score {
         effort,
         left_counter,
         right_counter,
-        switch,
+        switch_counter,
+        alternations,
+        trigram_rolls,
+        redirects,
+        one_hand_runs,
+        shift_counter,
         left_effort,
         right_effort,
-    )
+        factor,
+        finger_loads,
+        finger_deviation,
+        pinky_overload,
+        inward_rolls,
+        outward_rolls,
+        same_finger_bigrams: same_finger_bigrams.to_vec(),
+    }
 }
 
-fn calculate_word_score(
-    behaviour: &Behaviour,
-    keyboard: &HashMap<char, Position>,
-    word: &str,
-) -> Score {
-    #[inline]
-    fn is_left(position: Position) -> bool {
-        position < 15
-    }
-
-    let chars = word.chars().collect_vec();
-    let key = keyboard[&chars[0]];
-    let first = behaviour.efforts[&key][&key]; // to count the score for the first or one letter
-    let (score, left, right, switch, left_effort, right_effort) = chars
+/// Key presses of each finger.
+fn calculate_finger_loads(behaviour: &Behaviour, keyboard: &Keys) -> Vec<u64> {
+    let mut loads = vec![0; FINGERS_COUNT];
+
+    // thumbs are not counted among fingers.
+    for &(letter, count) in behaviour.corpus.letters.iter() {
+        match keyboard.get(&letter) {
+            Some(key) if !behaviour.geometry.is_thumb(*key) => {
+                loads[behaviour.fingers[key] as usize] += count
+            }
+            _ => {}
+        }
+    }
+
+    // pinkies press shift keys.
+    for &(letter, count) in behaviour.corpus.capitals.iter() {
+        if let Some((shift_is_left, _)) = behaviour
+            .shift_keys
+            .get_shift(behaviour.geometry.is_left(keyboard[&letter]))
+        {
+            loads[PINKIES[if shift_is_left { 0 } else { 1 }] as usize] += count;
+        }
+    }
+
+    loads
+}
+
+/// The sum of differences between shares of loads and targets
+/// and how much shares of pinkies exceed their targets.
+fn calculate_finger_deviation(loads: &[u64], targets: &[f64]) -> (f64, f64) {
+    let total: u64 = loads.iter().sum();
+
+    if total == 0 {
+        return (0., 0.);
+    }
+
+    let shares = loads.iter().map(|&x| x as f64 / total as f64).collect_vec();
+    let deviation = shares
         .iter()
-        .tuple_windows()
-        .map(|(a, b)| {
-            let key_a = keyboard[a];
-            let key_b = keyboard[b];
-            let a_is_left = is_left(key_a);
-            let b_is_left = is_left(key_b);
-            let both_left = a_is_left && b_is_left;
-            let both_right = !a_is_left && !b_is_left;
-            let switch = a_is_left != b_is_left;
-
-            if switch {
-                // key "a" is counted in a previous iteration,
-                // so whe we have the hand switch we need to count effort on the second letters,
-                // because the next hand "start" typing.
-                let effort = behaviour.efforts[&key_b][&key_b];
-
-                return (
-                    behaviour.switch_penalty * effort,
-                    both_left as u32,
-                    both_right as u32,
-                    switch as u32,
-                    if both_left { effort } else { 0. },
-                    if both_right { effort } else { 0. },
-                );
+        .zip(targets.iter())
+        .map(|(share, target)| (share - target).abs())
+        .sum();
+    let pinky_overload = PINKIES
+        .iter()
+        .map(|&x| (shares[x as usize] - targets[x as usize]).max(0.))
+        .sum();
+
+    (deviation, pinky_overload)
+}
+
+/// A sum over counted parts of the text, the same as walking all words.
+fn calculate_partial_score(behaviour: &Behaviour, keyboard: &Keys) -> PartialScore {
+    let corpus = &behaviour.corpus;
+    let initials = corpus.initials.iter().map(|&(letter, count)| {
+        let key = keyboard[&letter];
+
+        // to count the score for the first or one letter
+        PartialScore {
+            effort: behaviour.get_effort(key, key),
+            ..PartialScore::default()
+        }
+        .scale(count)
+    });
+    // spaces are between letters, the middle one of a trigram goes after the previous letter.
+    let key = |letter: char, neighbour: char| get_key(behaviour, keyboard, letter, neighbour);
+    let bigrams = corpus.bigrams.iter().map(|&((a, b), count)| {
+        calculate_bigram_score(behaviour, key(a, b), key(b, a)).scale(count)
+    });
+    let trigrams = corpus.trigrams.iter().map(|&((a, b, c), count)| {
+        calculate_trigram_score(behaviour, key(a, b), key(b, a), key(c, b)).scale(count)
+    });
+    let capitals = corpus.capitals.iter().map(|&(letter, count)| {
+        match behaviour
+            .shift_keys
+            .get_shift(behaviour.geometry.is_left(keyboard[&letter]))
+        {
+            Some((_, effort)) => PartialScore {
+                effort,
+                shift_counter: 1,
+                ..PartialScore::default()
             }
+            .scale(count),
+            None => PartialScore::default(),
+        }
+    });
 
-            let effort = behaviour.efforts[&key_a][&key_b];
-
-            if key_a == key_b {
-                return (
-                    effort * behaviour.same_key_penalty,
-                    both_left as u32,
-                    both_right as u32,
-                    switch as u32,
-                    if both_left { effort } else { 0. },
-                    if both_right { effort } else { 0. },
-                );
+    initials
+        .chain(bigrams)
+        .chain(trigrams)
+        .chain(capitals)
+        .fold(PartialScore::default(), PartialScore::add)
+}
+
+/// The key of a letter, the thumb of the space can depend on the neighbour letter.
+fn get_key(behaviour: &Behaviour, keyboard: &Keys, letter: char, neighbour: char) -> Position {
+    if letter == SPACE {
+        behaviour.get_space_key(keyboard[&neighbour])
+    } else {
+        keyboard[&letter]
+    }
+}
+
+fn calculate_bigram_score(behaviour: &Behaviour, key_a: Position, key_b: Position) -> PartialScore {
+    let mut score = PartialScore::default();
+    let a_is_left = behaviour.geometry.is_left(key_a);
+
+    if a_is_left != behaviour.geometry.is_left(key_b) {
+        // key "a" is counted in a previous iteration,
+        // so whe we have the hand switch we need to count effort on the second letters,
+        // because the next hand "start" typing.
+        score.switch_counter += 1;
+        score.effort += behaviour.switch_penalty * behaviour.get_effort(key_b, key_b);
+        return score;
+    }
+
+    let effort = behaviour.get_effort(key_a, key_b);
+    let penalty = if key_a == key_b {
+        behaviour.same_key_penalty
+    } else if behaviour.is_same_finger(key_a, key_b) {
+        score.same_finger_bigrams[behaviour.fingers[&key_a] as usize] += 1;
+        behaviour.same_finger_penalty
+    } else {
+        match behaviour.get_roll(key_a, key_b) {
+            Some(Roll::Inward) => {
+                score.inward_rolls += 1;
+                behaviour.inward_roll_weight
+            }
+            Some(Roll::Outward) => {
+                score.outward_rolls += 1;
+                behaviour.outward_roll_weight
             }
+            None => 1.,
+        }
+    };
 
-            (
-                effort,
-                both_left as u32,
-                both_right as u32,
-                switch as u32,
-                if both_left { effort } else { 0. },
-                if both_right { effort } else { 0. },
-            )
-        })
-        .fold(
-            (0., 0, 0, 0, 0., 0.),
-            |(total, left, right, total_switch, total_left_effort, total_right_effort),
-             (effort, both_left, both_right, switch, left_effort, right_effort)| {
-                (
-                    effort + total,
-                    left + both_left,
-                    right + both_right,
-                    total_switch + switch,
-                    total_left_effort + left_effort,
-                    total_right_effort + right_effort,
-                )
+    score.effort += effort * penalty;
+    if a_is_left {
+        score.left_counter += 1;
+        score.left_effort += effort;
+    } else {
+        score.right_counter += 1;
+        score.right_effort += effort;
+    }
+
+    score
+}
+
+fn calculate_trigram_score(
+    behaviour: &Behaviour,
+    key_a: Position,
+    key_b: Position,
+    key_c: Position,
+) -> PartialScore {
+    let mut score = PartialScore::default();
+    let trigram = behaviour.get_trigram(key_a, key_b, key_c);
+
+    match trigram {
+        Trigram::Alternation => score.alternations += 1,
+        Trigram::Roll => score.trigram_rolls += 1,
+        Trigram::Redirect => score.redirects += 1,
+        Trigram::OneHand => score.one_hand_runs += 1,
+    }
+    score.effort += behaviour.trigram_weights.get(trigram);
+
+    score
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::behaviour::{
+        generator::generate, tests::default_behaviour, Corpus, ShiftKeys, Thumbs, TrigramWeights,
+        Words,
+    };
+    use itertools::Itertools;
+
+    /// Walks words one by one like the score was calculated before the corpus.
+    fn walk_words(behaviour: &Behaviour, keyboard: &Keys, words: &Words) -> PartialScore {
+        words
+            .iter()
+            .map(|(word, count)| walk_word(behaviour, keyboard, word).scale(*count))
+            .fold(PartialScore::default(), PartialScore::add)
+    }
+
+    /// Walks words with spaces around them, each word is followed by each word
+    /// in proportion to their counts, the counts have to divide evenly.
+    fn walk_words_with_spaces(
+        behaviour: &Behaviour,
+        keyboard: &Keys,
+        words: &Words,
+    ) -> PartialScore {
+        let total: u64 = words.iter().map(|(_, count)| count).sum();
+        let lowercase = |letter: Option<char>| letter.unwrap().to_ascii_lowercase();
+        let between =
+            words
+                .iter()
+                .cartesian_product(words.iter())
+                .map(|((previous, a), (next, b))| {
+                    assert_eq!(a * b % total, 0);
+                    let last = keyboard[&lowercase(previous.chars().last())];
+                    let first = keyboard[&lowercase(next.chars().next())];
+                    let space = behaviour.get_space_key(last);
+
+                    walk_trigram(behaviour, last, space, first).scale(a * b / total)
+                });
+
+        words
+            .iter()
+            .map(|(word, count)| (format!(" {} ", word), *count))
+            .map(|(word, count)| walk_word(behaviour, keyboard, &word).scale(count))
+            .chain(between)
+            .fold(PartialScore::default(), PartialScore::add)
+    }
+
+    fn walk_word(behaviour: &Behaviour, keyboard: &Keys, word: &str) -> PartialScore {
+        let chars = word.chars().collect_vec();
+        let letters = chars.iter().map(|x| x.to_ascii_lowercase()).collect_vec();
+        // the space is pressed by the thumb of the hand of the next letter or the previous one.
+        let key = |index: usize, neighbour: usize| {
+            get_key(behaviour, keyboard, letters[index], letters[neighbour])
+        };
+        let mut score = PartialScore::default();
+
+        if letters[0] != SPACE {
+            let key = keyboard[&letters[0]];
+            score.effort += behaviour.get_effort(key, key);
+        }
+
+        for index in 1..letters.len() {
+            let key_a = key(index - 1, index);
+            let key_b = key(index, index - 1);
+            let a_is_left = behaviour.geometry.is_left(key_a);
+
+            if a_is_left != behaviour.geometry.is_left(key_b) {
+                score.switch_counter += 1;
+                score.effort += behaviour.switch_penalty * behaviour.get_effort(key_b, key_b);
+                continue;
+            }
+
+            let effort = behaviour.get_effort(key_a, key_b);
+            let penalty = if key_a == key_b {
+                behaviour.same_key_penalty
+            } else if behaviour.is_same_finger(key_a, key_b) {
+                score.same_finger_bigrams[behaviour.fingers[&key_a] as usize] += 1;
+                behaviour.same_finger_penalty
+            } else {
+                match behaviour.get_roll(key_a, key_b) {
+                    Some(Roll::Inward) => {
+                        score.inward_rolls += 1;
+                        behaviour.inward_roll_weight
+                    }
+                    Some(Roll::Outward) => {
+                        score.outward_rolls += 1;
+                        behaviour.outward_roll_weight
+                    }
+                    None => 1.,
+                }
+            };
+
+            score.effort += effort * penalty;
+            if a_is_left {
+                score.left_counter += 1;
+                score.left_effort += effort;
+            } else {
+                score.right_counter += 1;
+                score.right_effort += effort;
+            }
+        }
+
+        for index in 2..letters.len() {
+            let trigram = walk_trigram(
+                behaviour,
+                key(index - 2, index - 1),
+                key(index - 1, index - 2),
+                key(index, index - 1),
+            );
+            score = score.add(trigram);
+        }
+
+        for &letter in chars.iter().filter(|x| x.is_uppercase()) {
+            let letter_is_left = behaviour
+                .geometry
+                .is_left(keyboard[&letter.to_ascii_lowercase()]);
+            if let Some((_, effort)) = behaviour.shift_keys.get_shift(letter_is_left) {
+                score.shift_counter += 1;
+                score.effort += effort;
+            }
+        }
+
+        score
+    }
+
+    fn walk_trigram(
+        behaviour: &Behaviour,
+        key_a: Position,
+        key_b: Position,
+        key_c: Position,
+    ) -> PartialScore {
+        let mut score = PartialScore::default();
+        let trigram = behaviour.get_trigram(key_a, key_b, key_c);
+
+        match trigram {
+            Trigram::Alternation => score.alternations += 1,
+            Trigram::Roll => score.trigram_rolls += 1,
+            Trigram::Redirect => score.redirects += 1,
+            Trigram::OneHand => score.one_hand_runs += 1,
+        }
+        score.effort += behaviour.trigram_weights.get(trigram);
+
+        score
+    }
+
+    fn assert_walked(actual: PartialScore, expected: PartialScore) {
+        assert!((actual.effort - expected.effort).abs() < 1e-6 * expected.effort);
+        assert!((actual.left_effort - expected.left_effort).abs() < 1e-6 * expected.left_effort);
+        assert!((actual.right_effort - expected.right_effort).abs() < 1e-6 * expected.right_effort);
+        assert_eq!(
+            PartialScore {
+                effort: 0.,
+                left_effort: 0.,
+                right_effort: 0.,
+                ..actual
             },
+            PartialScore {
+                effort: 0.,
+                left_effort: 0.,
+                right_effort: 0.,
+                ..expected
+            }
         );
+    }
 
-    (
-        score + first,
-        left,
-        right,
-        switch,
-        left_effort,
-        right_effort,
-    )
+    fn read_sample_words() -> Words {
+        let text = std::fs::read_to_string("data/samples/0.1-29.result.txt").unwrap();
+
+        text.split_whitespace()
+            .map(|x| (x.to_string(), 1))
+            .collect()
+    }
+
+    /// Efforts, fingers and weights which make components of the score differ.
+    fn sample_behaviour() -> Behaviour {
+        let mut behaviour = default_behaviour();
+        behaviour.efforts = (0..30)
+            .map(|a| {
+                let efforts = (0..30)
+                    .map(|b| (b, 1. + ((a as usize * 7 + b as usize * 3) % 5) as f64 * 0.5))
+                    .collect();
+                (a, efforts)
+            })
+            .collect();
+        behaviour.fingers = (0..30)
+            .map(|x| {
+                let finger = [0, 1, 2, 3, 3][x as usize % 5];
+                (x, if x < 15 { finger } else { 7 - finger })
+            })
+            .collect();
+        behaviour.blocked_keys = [30, 31].iter().copied().collect();
+        behaviour.inward_roll_weight = 0.8;
+        behaviour.outward_roll_weight = 0.9;
+        behaviour.same_finger_penalty = 2.;
+        behaviour.trigram_weights = TrigramWeights {
+            alternation: -0.1,
+            roll: -0.2,
+            redirect: 1.,
+            one_hand: 0.5,
+        };
+
+        behaviour
+    }
+
+    /// The keyboard of the sample text and random ones.
+    fn sample_keyboards(behaviour: &Behaviour) -> Vec<Keys> {
+        let positions = (0..30)
+            .filter(|x| ![15, 25, 26, 27].contains(x))
+            .collect_vec();
+        let keyboard: Keys = ('a'..='z').zip(positions.iter().copied()).collect();
+
+        (0..5)
+            .map(|_| generate(behaviour).keys)
+            .chain(std::iter::once(keyboard))
+            .collect()
+    }
+
+    #[test]
+    fn should_match_walking_words() {
+        let words = read_sample_words();
+        let mut behaviour = sample_behaviour();
+        behaviour.corpus = Corpus::new(&words);
+
+        for keyboard in sample_keyboards(&behaviour) {
+            let expected = walk_words(&behaviour, &keyboard, &words);
+            let actual = calculate_partial_score(&behaviour, &keyboard);
+
+            assert_walked(actual, expected);
+        }
+    }
+
+    #[test]
+    fn should_match_walking_words_with_spaces() {
+        // each word follows each word once.
+        let words: Words = [
+            "the", "quick", "brown", "fox", "jumps", "over", "lazy", "dog",
+        ]
+        .iter()
+        .map(|x| (x.to_string(), 8))
+        .collect();
+
+        for space in [[30, 31], [30, 30], [31, 31]].iter() {
+            let mut behaviour = sample_behaviour();
+            behaviour.corpus = Corpus::with_spaces(&words);
+            behaviour.thumbs = Some(Thumbs {
+                space: *space,
+                effort: 1.5,
+            });
+
+            for keyboard in sample_keyboards(&behaviour) {
+                let expected = walk_words_with_spaces(&behaviour, &keyboard, &words);
+                let actual = calculate_partial_score(&behaviour, &keyboard);
+
+                assert_walked(actual, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn should_match_walking_capitals() {
+        let words: Words = read_sample_words()
+            .into_iter()
+            .enumerate()
+            .map(|(index, (word, count))| match index % 3 {
+                0 => (word.to_uppercase(), count),
+                1 => (word[..1].to_uppercase() + &word[1..], count),
+                _ => (word, count),
+            })
+            .collect();
+
+        for right in [Some(3.), None].iter() {
+            let mut behaviour = sample_behaviour();
+            behaviour.corpus = Corpus::new(&words);
+            behaviour.shift_keys = ShiftKeys {
+                left: Some(2.),
+                right: *right,
+                coordination: 1.,
+                same_hand_penalty: 3.,
+            };
+
+            for keyboard in sample_keyboards(&behaviour) {
+                let expected = walk_words(&behaviour, &keyboard, &words);
+                let actual = calculate_partial_score(&behaviour, &keyboard);
+
+                assert_walked(actual, expected);
+            }
+        }
+    }
+
+    /// A change of the default behaviour and a check of its score against the score before it.
+    struct Case {
+        name: &'static str,
+        keys: &'static [(char, u8)],
+
+        /// Applied before both scores.
+        setup: fn(&mut Behaviour),
+        change: fn(&mut Behaviour),
+        check: fn(&str, &Score, &Score),
+    }
+
+    const ABC: &[(char, u8)] = &[('a', 0), ('b', 1), ('c', 2)];
+
+    fn with_shift_keys(behaviour: &mut Behaviour) {
+        behaviour.corpus = Corpus::new(&vec![("Abc".to_string(), 1)]);
+        behaviour.shift_keys = ShiftKeys {
+            left: Some(2.),
+            right: Some(3.),
+            coordination: 1.,
+            same_hand_penalty: 3.,
+        };
+    }
+
+    /// The space is typed by the left thumb.
+    fn with_spaces(behaviour: &mut Behaviour) {
+        behaviour.corpus = Corpus::with_spaces(&vec![("ab".to_string(), 1)]);
+        behaviour.thumbs = Some(Thumbs {
+            space: [30, 30],
+            effort: 1.,
+        });
+    }
+
+    #[test]
+    fn should_weight_score_components() {
+        let cases = [
+            Case {
+                name: "finger deviation",
+                keys: ABC,
+                setup: |_| {},
+                change: |x| {
+                    x.finger_targets = Some(vec![0.125; 8]);
+                    x.finger_penalty = 1.;
+                },
+                check: |name, actual, expected| {
+                    assert_eq!(
+                        actual.finger_loads,
+                        vec![1, 1, 1, 0, 0, 0, 0, 0],
+                        "{}",
+                        name
+                    );
+                    assert!((actual.finger_deviation - 1.25).abs() < 1e-9, "{}", name);
+                    assert!(
+                        (actual.pinky_overload - (1. / 3. - 0.125)).abs() < 1e-9,
+                        "{}",
+                        name
+                    );
+                    assert!(
+                        (actual.effort - expected.effort * 2.25).abs() < 1e-9,
+                        "{}",
+                        name
+                    );
+                },
+            },
+            Case {
+                name: "rolls",
+                keys: ABC,
+                setup: |_| {},
+                change: |x| x.inward_roll_weight = 0.5,
+                check: |name, actual, _| {
+                    assert_eq!(actual.inward_rolls, 2, "{}", name);
+                    assert_eq!(actual.outward_rolls, 0, "{}", name);
+                    assert_eq!(
+                        actual.effort,
+                        (1. + (2. + 6.) * 0.5) * actual.factor,
+                        "{}",
+                        name
+                    );
+                    assert_eq!(actual.left_effort, 8., "{}", name);
+                },
+            },
+            Case {
+                name: "same finger bigrams",
+                keys: ABC,
+                setup: |_| {},
+                change: |x| {
+                    x.fingers.insert(1, 0);
+                    x.same_finger_penalty = 2.;
+                },
+                check: |name, actual, _| {
+                    assert_eq!(
+                        actual.same_finger_bigrams,
+                        vec![1, 0, 0, 0, 0, 0, 0, 0],
+                        "{}",
+                        name
+                    );
+                    assert_eq!(actual.inward_rolls, 1, "{}", name);
+                    assert_eq!(
+                        actual.effort,
+                        (1. + 2. * 2. + 6.) * actual.factor,
+                        "{}",
+                        name
+                    );
+                },
+            },
+            Case {
+                name: "trigrams",
+                keys: ABC,
+                setup: |_| {},
+                change: |x| x.trigram_weights.one_hand = 10.,
+                check: |name, actual, _| {
+                    assert_eq!(actual.one_hand_runs, 1, "{}", name);
+                    assert_eq!(
+                        actual.alternations + actual.trigram_rolls + actual.redirects,
+                        0,
+                        "{}",
+                        name
+                    );
+                    assert_eq!(
+                        actual.effort,
+                        (1. + 2. + 6. + 10.) * actual.factor,
+                        "{}",
+                        name
+                    );
+                },
+            },
+            Case {
+                name: "word counts",
+                keys: ABC,
+                setup: |x| {
+                    x.corpus = Corpus::new(&vec![
+                        ("abcab".to_string(), 1),
+                        ("abcab".to_string(), 1),
+                        ("ca".to_string(), 1),
+                    ])
+                },
+                change: |x| {
+                    x.corpus = Corpus::new(&vec![("abcab".to_string(), 2), ("ca".to_string(), 1)])
+                },
+                check: |name, actual, expected| assert_eq!(actual, expected, "{}", name),
+            },
+            // without shift keys capitals are typed as lower case letters.
+            Case {
+                name: "capitals without shift keys",
+                keys: ABC,
+                setup: |_| {},
+                change: |x| x.corpus = Corpus::new(&vec![("Abc".to_string(), 1)]),
+                check: |name, actual, expected| assert_eq!(actual, expected, "{}", name),
+            },
+            Case {
+                name: "shift of the other hand",
+                keys: ABC,
+                setup: |_| {},
+                change: with_shift_keys,
+                check: |name, actual, expected| {
+                    assert_eq!(actual.shift_counter, 1, "{}", name);
+                    assert_eq!(actual.finger_loads[7], 1, "{}", name);
+                    assert_eq!(
+                        actual.effort,
+                        expected.effort + 4. * actual.factor,
+                        "{}",
+                        name
+                    );
+                },
+            },
+            Case {
+                name: "shift of the same hand",
+                keys: ABC,
+                setup: |_| {},
+                change: |x| {
+                    with_shift_keys(x);
+                    x.shift_keys.right = None;
+                },
+                check: |name, actual, expected| {
+                    assert_eq!(actual.finger_loads[7], 0, "{}", name);
+                    assert_eq!(
+                        actual.effort,
+                        expected.effort + 5. * actual.factor,
+                        "{}",
+                        name
+                    );
+                },
+            },
+            // " a" and "b " switch hands, "b a" between words alternates.
+            Case {
+                name: "spaces by both thumbs",
+                keys: &[('a', 0), ('b', 1)],
+                setup: with_spaces,
+                change: |x| x.thumbs.as_mut().unwrap().space = [31, 30],
+                check: |name, actual, _| {
+                    assert_eq!(actual.switch_counter, 2, "{}", name);
+                    assert_eq!(actual.alternations, 1, "{}", name);
+                    assert_eq!(actual.trigram_rolls, 2, "{}", name);
+                    assert_eq!(
+                        actual.effort,
+                        (3. * 1. + 2. + 3. * 1.) * actual.factor,
+                        "{}",
+                        name
+                    );
+                },
+            },
+            Case {
+                name: "spaces by the left thumb",
+                keys: &[('a', 0), ('b', 1)],
+                setup: with_spaces,
+                change: |_| {},
+                check: |name, actual, _| {
+                    assert_eq!(actual.switch_counter, 0, "{}", name);
+                    assert_eq!(actual.alternations, 0, "{}", name);
+                    assert_eq!(actual.effort, (1. + 2. + 1.) * actual.factor, "{}", name);
+                },
+            },
+        ];
+
+        for case in cases.iter() {
+            let keys: Keys = case.keys.iter().copied().collect();
+            let mut behaviour = default_behaviour();
+            (case.setup)(&mut behaviour);
+            let expected = calculate_score(&behaviour, &keys);
+
+            (case.change)(&mut behaviour);
+            let actual = calculate_score(&behaviour, &keys);
+
+            (case.check)(case.name, &actual, &expected);
+        }
+    }
+
+    #[test]
+    fn should_calculate_finger_deviation() {
+        let targets = [0.1, 0.1, 0.1, 0.2, 0.2, 0.1, 0.1, 0.1];
+
+        let (deviation, pinky_overload) =
+            calculate_finger_deviation(&[2, 1, 1, 2, 2, 1, 1, 0], &targets);
+
+        assert!((deviation - 0.2).abs() < 1e-9);
+        assert!((pinky_overload - 0.1).abs() < 1e-9);
+        assert_eq!(calculate_finger_deviation(&[0; 8], &targets), (0., 0.));
+    }
 }
@@ -1,7 +1,10 @@
-use crate::behaviour::Position;
-use ed_balance::{IIndividual, IMutation};
+use crate::{
+    behaviour::{Geometry, Position},
+    record::KeyboardRecord,
+};
+use ed_balance::{get_id, IIndividual, IMutation, ResultRecord};
 use itertools::Itertools;
-use std::{cmp::Ordering, collections::HashMap, hash::Hash, slice::Iter};
+use std::{cmp::Ordering, collections::HashMap, hash::Hash, sync::Arc};
 
 #[derive(Debug, Hash, Eq, PartialEq, PartialOrd, Clone, Copy)]
 pub struct Mutation {
@@ -12,19 +15,55 @@ pub struct Mutation {
 impl IMutation for Mutation {}
 
 pub type Keys = HashMap<char, Position>;
-pub type Score = (f64, u32, u32, u32, f64, f64);
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Score {
+    /// Total effort with the applied balance and finger factors.
+    pub effort: f64,
+    pub left_counter: u64,
+    pub right_counter: u64,
+    pub switch_counter: u64,
+
+    /// Counters of trigram classes.
+    pub alternations: u64,
+    pub trigram_rolls: u64,
+    pub redirects: u64,
+    pub one_hand_runs: u64,
+
+    /// Upper case letters typed with the shift.
+    pub shift_counter: u64,
+    pub left_effort: f64,
+    pub right_effort: f64,
+
+    /// The balance factor of left and right efforts.
+    pub factor: f64,
+
+    /// Key presses of each finger from the left pinky to the right one.
+    pub finger_loads: Vec<u64>,
+
+    /// Sum of differences between load shares of fingers and their targets.
+    pub finger_deviation: f64,
+
+    /// How much load shares of pinkies exceed their targets.
+    pub pinky_overload: f64,
+
+    /// Same row pairs of a hand from the pinky to the index finger.
+    pub inward_rolls: u64,
+
+    /// Same row pairs of a hand from the index finger to the pinky.
+    pub outward_rolls: u64,
+
+    /// Pairs of different keys typed by the same finger, for each finger.
+    pub same_finger_bigrams: Vec<u64>,
+}
 
 #[derive(Debug, Clone)]
 pub struct Keyboard {
     pub version: String,
 
-    /// A list of 30 characters.\
-    /// Each position represents a position on a keyboard.\
-    /// 0-14 the left part, 15-29 the right part.\
-    /// The numbering like in the `ergo-layout` app.\
-    /// Right part mirrored left.
-    /// `_` means a skipped and blocked key.
+    /// Positions of letters on keys of the geometry.
     pub keys: Keys,
+    pub geometry: Arc<Geometry>,
     pub score: Score,
 
     pub mutations: Vec<Mutation>,
@@ -40,13 +79,12 @@ impl Keyboard {
         mutations: Vec<Mutation>,
         parent_version: String,
         parent: Keys,
+        geometry: Arc<Geometry>,
     ) -> Box<Keyboard> {
-        debug_assert_eq!(keys.len(), 26);
-        debug_assert_eq!(keys.values().max().unwrap(), &29_u8);
-
         box_keyboard(Keyboard {
             version,
             keys,
+            geometry,
             mutations,
             parent_version,
             parent,
@@ -77,7 +115,7 @@ impl Eq for Keyboard {}
 
 impl Hash for Keyboard {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        for (&c, &p) in self.keys.iter().sorted_by_key(|(&c, _)| c).into_iter() {
+        for (&c, &p) in self.keys.iter().sorted_by_key(|(&c, _)| c) {
             c.hash(state);
             p.hash(state);
         }
@@ -90,83 +128,47 @@ impl IIndividual<Mutation> for Keyboard {
     }
 
     fn to_string(&self) -> String {
-        fn fill_missed_positions(iter: Iter<(char, u8)>, revert: bool) -> String {
-            let inverted: HashMap<_, _> = iter.map(|(c, p)| (p % 5_u8, *c)).collect();
-
-            (0_u8..=4_u8)
-                .map(|i| if revert { 4 - i } else { i })
-                .map(|i| match inverted.get(&i) {
-                    Some(&c) => c,
-                    _ => '_',
-                })
-                .join("")
-        }
-
-        let sorted = self
-            .keys
-            .iter()
-            .sorted_by(|(_, i1), (_, i2)| i1.cmp(i2))
-            .collect_vec();
-
-        let left = sorted
-            .iter()
-            .filter(|(_, &p)| p < 15)
-            .group_by(|(_, &p)| p / 5)
-            .into_iter()
-            .map(|(_, x)| {
-                fill_missed_positions(x.map(|(&a, &b)| (a, b)).collect_vec().iter(), false)
-            })
-            .join(" ");
-
-        let right = sorted
-            .iter()
-            .filter(|(_, &p)| p >= 15)
-            .group_by(|(_, &p)| p / 5)
-            .into_iter()
-            .map(|(_, x)| {
-                let sorted = x
-                    .sorted_by(|(_, &i1), (_, &i2)| i2.cmp(&i1))
-                    .map(|(&a, &b)| (a, b))
-                    .collect_vec();
-
-                fill_missed_positions(sorted.iter(), true)
-            })
-            .join(" ");
-
-        let (effort, left_counter, right_counter, switch, left_effort, right_effort) = self.score;
-        format!(
-            "{}  {};{};{};{};{:.3};{:.2};{:.2};{:.3};{:.2}",
-            left,
-            right,
-            left_counter,
-            right_counter,
-            switch,
-            get_balance(left_counter as f64, right_counter as f64),
-            left_effort,
-            right_effort,
-            get_factor(left_effort, right_effort),
-            effort
-        )
+        self.to_record().to_legacy()
     }
 
     fn get_score(&self) -> f64 {
-        self.score.0
+        self.score.effort
     }
 }
 
-fn box_keyboard(keyboard: Keyboard) -> Box<Keyboard> {
-    Box::new(keyboard)
+impl Keyboard {
+    pub fn to_record(&self) -> KeyboardRecord {
+        let score = &self.score;
+        let layout = self.geometry.format_layout(&self.keys);
+
+        KeyboardRecord {
+            id: get_id(&layout),
+            layout,
+            effort: score.effort,
+            left_counter: score.left_counter,
+            right_counter: score.right_counter,
+            switch_counter: score.switch_counter,
+            alternations: score.alternations,
+            trigram_rolls: score.trigram_rolls,
+            redirects: score.redirects,
+            one_hand_runs: score.one_hand_runs,
+            shift_counter: score.shift_counter,
+            counter_balance: get_balance(score.left_counter as f64, score.right_counter as f64),
+            left_effort: score.left_effort,
+            right_effort: score.right_effort,
+            factor: score.factor,
+            finger_deviation: score.finger_deviation,
+            pinky_overload: score.pinky_overload,
+            finger_loads: score.finger_loads.clone(),
+            inward_rolls: score.inward_rolls,
+            outward_rolls: score.outward_rolls,
+            same_finger_bigrams: score.same_finger_bigrams.clone(),
+        }
+    }
 }
 
-/// better the ballance lower the factor.\
-/// the ideal factor is 1 for the ideal balance (50x50).\
-/// 1 means that the factor does not affect a score.
-pub fn get_factor(left_score: f64, right_score: f64) -> f64 {
-    let ballance = get_balance(left_score, right_score);
-
-    // https://www.desmos.com/calculator
-    // bigger power - less strict ballance
-    3. - (2. / ((ballance - 1.).powi(2) + 1.))
+fn box_keyboard(keyboard: Keyboard) -> Box<Keyboard> {
+    Box::new(keyboard)
 }
 
 fn get_balance(left_score: f64, right_score: f64) -> f64 {
@@ -198,8 +200,12 @@ pub mod tests {
                 .cloned()
                 .collect(),
             parent_version: "parent_version".to_string(),
-            score: (1., 1, 2, 3, 4., 5.),
+            score: Score {
+                effort: 1.,
+                ..Score::default()
+            },
             version: "version".to_string(),
+            geometry: Arc::new(Geometry::default()),
         };
 
         let k2 = Keyboard {
@@ -214,8 +220,12 @@ pub mod tests {
             .to_vec(),
             parent: [('a', 0_u8)].iter().cloned().collect(),
             parent_version: "parent_version2".to_string(),
-            score: (2., 3, 4, 5, 6., 7.),
+            score: Score {
+                effort: 2.,
+                ..Score::default()
+            },
             version: "version2".to_string(),
+            geometry: Arc::new(Geometry::default()),
         };
 
         assert_eq!(&k1, &k2);
@@ -1,5 +1,6 @@
 mod behaviour;
 mod keyboard;
+mod record;
 
 use behaviour::Behaviour;
 use ed_balance::{run, CliSettings};
@@ -16,6 +17,18 @@ use structopt::StructOpt;
 
 fn main() {
     let args = CliSettings::from_args();
+    match behaviour::validate(&args) {
+        Ok(summary) if args.validate => {
+            println!("{}", summary);
+            return;
+        }
+        Ok(_) => (),
+        Err(e) => {
+            eprintln!("Invalid keyboard settings:\n{}", e);
+            process::exit(1);
+        }
+    }
+
     if let Err(e) = run::<Mutation, Keyboard, Behaviour>(args) {
         eprintln!("Calculations failed: {:#?}", e);
         process::exit(1);
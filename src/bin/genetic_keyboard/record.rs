@@ -0,0 +1,232 @@
+use ed_balance::{get_id, nested, ResultRecord};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+/// A keyboard layout with all metrics of its `Score`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyboardRecord {
+    /// A hash of the layout, the same layout has the same id in all runs.
+    #[serde(default)]
+    pub id: String,
+
+    /// Rows of the left part, then mirrored rows of the right part.\
+    /// Like `jpmyq asreb zxcvk  whtu_ dnloi gf___`.
+    pub layout: String,
+
+    /// Total effort with the applied balance factor.
+    pub effort: f64,
+    pub left_counter: u64,
+    pub right_counter: u64,
+    pub switch_counter: u64,
+
+    /// Counters of trigram classes.
+    #[serde(default)]
+    pub alternations: u64,
+    #[serde(default)]
+    pub trigram_rolls: u64,
+    #[serde(default)]
+    pub redirects: u64,
+    #[serde(default)]
+    pub one_hand_runs: u64,
+
+    /// Upper case letters typed with the shift.
+    #[serde(default)]
+    pub shift_counter: u64,
+
+    /// `max / min` of left and right counters.
+    pub counter_balance: f64,
+    pub left_effort: f64,
+    pub right_effort: f64,
+
+    /// The balance factor of left and right efforts.
+    pub factor: f64,
+
+    /// Sum of differences between load shares of fingers and their targets.
+    #[serde(default)]
+    pub finger_deviation: f64,
+
+    /// How much load shares of pinkies exceed their targets.
+    #[serde(default)]
+    pub pinky_overload: f64,
+
+    /// Key presses of each finger from the left pinky to the right one.
+    #[serde(default, deserialize_with = "nested")]
+    pub finger_loads: Vec<u64>,
+
+    /// Same row pairs of a hand from the pinky to the index finger.
+    #[serde(default)]
+    pub inward_rolls: u64,
+
+    /// Same row pairs of a hand from the index finger to the pinky.
+    #[serde(default)]
+    pub outward_rolls: u64,
+
+    /// Pairs of different keys typed by the same finger, for each finger.
+    #[serde(default, deserialize_with = "nested")]
+    pub same_finger_bigrams: Vec<u64>,
+}
+
+impl KeyboardRecord {
+    /// Parses a line of `to_legacy`.\
+    /// Older files have other metrics, for them only the layout is kept
+    /// with empty metrics, they are recalculated on load anyway.\
+    /// The id is calculated from the layout, lines before ids have no it.
+    pub fn from_legacy(line: &str) -> Option<KeyboardRecord> {
+        let parts = line.split(';').map(|x| x.trim()).collect_vec();
+        let layout = parts.first().filter(|x| !x.is_empty())?.to_string();
+        let id = get_id(&layout);
+
+        // the id is the last field, lines before it have only 9 fields.
+        // metrics after the effort are missed in lines written before them.
+        let fields = if parts.len() > 9 {
+            &parts[..parts.len() - 1]
+        } else {
+            &parts[..]
+        };
+        let metrics = || -> Option<KeyboardRecord> {
+            let number = |i: usize| fields.get(i)?.parse::<f64>().ok();
+            let counter = |i: usize| fields.get(i)?.parse::<u64>().ok();
+            let optional_number = |i: usize| {
+                if i < fields.len() {
+                    number(i)
+                } else {
+                    Some(0.)
+                }
+            };
+            let optional_counter = |i: usize| {
+                if i < fields.len() {
+                    counter(i)
+                } else {
+                    Some(0)
+                }
+            };
+            let optional_counters = |i: usize| match fields.get(i) {
+                Some(counters) => counters
+                    .split(',')
+                    .map(|x| x.parse::<u64>().ok())
+                    .collect::<Option<Vec<_>>>(),
+                None => Some(Vec::new()),
+            };
+
+            Some(KeyboardRecord {
+                id: id.clone(),
+                layout: layout.clone(),
+                left_counter: counter(1)?,
+                right_counter: counter(2)?,
+                switch_counter: counter(3)?,
+                counter_balance: number(4)?,
+                left_effort: number(5)?,
+                right_effort: number(6)?,
+                factor: number(7)?,
+                effort: number(8)?,
+                finger_deviation: optional_number(9)?,
+                pinky_overload: optional_number(10)?,
+                finger_loads: optional_counters(11)?,
+                inward_rolls: optional_counter(12)?,
+                outward_rolls: optional_counter(13)?,
+                same_finger_bigrams: optional_counters(14)?,
+                alternations: optional_counter(15)?,
+                trigram_rolls: optional_counter(16)?,
+                redirects: optional_counter(17)?,
+                one_hand_runs: optional_counter(18)?,
+                shift_counter: optional_counter(19)?,
+            })
+        };
+
+        let record = metrics();
+
+        Some(record.unwrap_or(KeyboardRecord {
+            id,
+            layout,
+            ..KeyboardRecord::default()
+        }))
+    }
+}
+
+impl ResultRecord for KeyboardRecord {
+    fn to_legacy(&self) -> String {
+        format!(
+            "{};{};{};{};{:.3};{:.2};{:.2};{:.3};{:.2};{:.3};{:.3};{};{};{};{};{};{};{};{};{};{}",
+            self.layout,
+            self.left_counter,
+            self.right_counter,
+            self.switch_counter,
+            self.counter_balance,
+            self.left_effort,
+            self.right_effort,
+            self.factor,
+            self.effort,
+            self.finger_deviation,
+            self.pinky_overload,
+            self.finger_loads.iter().join(","),
+            self.inward_rolls,
+            self.outward_rolls,
+            self.same_finger_bigrams.iter().join(","),
+            self.alternations,
+            self.trigram_rolls,
+            self.redirects,
+            self.one_hand_runs,
+            self.shift_counter,
+            self.id
+        )
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use ed_balance::{format_records, parse_records, OutputFormat};
+
+    #[test]
+    fn should_parse_legacy_line() {
+        let line = "jpmyq asreb zxcvk  whtu_ dnloi gf___;100872;105820;170157;1.049;136853.00;134955.50;1.000;1346342.75;0.120;0.015;1,2,3,4,5,6,7,8;10;20;0,1,2,0,0,3,4,0;5;6;7;8;9;c6749705d3";
+
+        let actual = KeyboardRecord::from_legacy(line).unwrap();
+
+        assert_eq!(actual.id, "c6749705d3");
+        assert_eq!(actual.layout, "jpmyq asreb zxcvk  whtu_ dnloi gf___");
+        assert_eq!(actual.switch_counter, 170157);
+        assert_eq!(actual.effort, 1346342.75);
+        assert_eq!(actual.pinky_overload, 0.015);
+        assert_eq!(actual.finger_loads, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(actual.outward_rolls, 20);
+        assert_eq!(actual.same_finger_bigrams, vec![0, 1, 2, 0, 0, 3, 4, 0]);
+        assert_eq!(actual.one_hand_runs, 8);
+        assert_eq!(actual.shift_counter, 9);
+        assert_eq!(actual.to_legacy(), line);
+    }
+
+    #[test]
+    fn should_parse_lines_without_finger_metrics() {
+        let line = "jpmyq asreb zxcvk  whtu_ dnloi gf___;100872;105820;170157;1.049;136853.00;134955.50;1.000;1346342.75;c6749705d3";
+
+        let actual = KeyboardRecord::from_legacy(line).unwrap();
+
+        assert_eq!(actual.effort, 1346342.75);
+        assert_eq!(actual.finger_deviation, 0.);
+        assert!(actual.finger_loads.is_empty());
+    }
+
+    #[test]
+    fn should_keep_layout_of_old_lines() {
+        let line = "jmdpq asreb zxcvk  wyth_ uilno fg___;1628;1571;1.036;1.003;14440.87119";
+
+        let actual = KeyboardRecord::from_legacy(line).unwrap();
+
+        assert_eq!(actual.layout, "jmdpq asreb zxcvk  wyth_ uilno fg___");
+        assert_eq!(actual.effort, 0.);
+        assert_eq!(actual.id, get_id(&actual.layout));
+        assert_eq!(KeyboardRecord::from_legacy(""), None);
+    }
+
+    #[test]
+    fn should_read_finger_metrics_from_csv() {
+        let line = "jpmyq asreb zxcvk  whtu_ dnloi gf___;100872;105820;170157;1.049;136853.00;134955.50;1.000;1346342.75;0.120;0.015;1,2,3,4,5,6,7,8;10;20;0,1,2,0,0,3,4,0;5;6;7;8;9;c6749705d3";
+        let records = vec![KeyboardRecord::from_legacy(line).unwrap()];
+        let csv = format_records(&records, OutputFormat::Csv).unwrap();
+
+        let actual = parse_records(&csv, KeyboardRecord::from_legacy).unwrap();
+
+        assert_eq!(actual, records);
+    }
+}
@@ -1,24 +1,112 @@
 use super::{Letters, LettersPointer, Mutation};
 use ed_balance::{
-    calculate_score, get_version,
-    models::{CliSettings, Digraphs},
-    Context, IBehaviour,
+    calculate_score, format_records, get_version,
+    models::{BalancePenalty, CliSettings, Digraphs, GroupSize, LettersRecord, OutputFormat},
+    parse_records, Context, IBehaviour,
 };
-use itertools::{min, Itertools};
+use itertools::Itertools;
 use rand::{prelude::SliceRandom, thread_rng};
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+const LETTERS_COUNT: usize = 26;
 
 pub struct Behaviour {
     pub context: Context,
     pub digraphs: Digraphs,
     pub frozen_left: HashSet<char>,
     pub frozen_right: HashSet<char>,
+    pub balance_penalty: BalancePenalty,
+
+    /// Allowed sizes of each group, the balance curve decides the best one.
+    pub left_size: GroupSize,
+    pub right_size: GroupSize,
+
+    /// The format of saved letters.
+    pub output_format: OutputFormat,
+
+    /// Results of previous runs to start with.
+    pub seed: Option<PathBuf>,
+}
+
+impl Behaviour {
+    fn fits(&self, left_count: usize, right_count: usize) -> bool {
+        self.left_size.contains(left_count) && self.right_size.contains(right_count)
+    }
+
+    /// Left group sizes that fit both bounds and frozen letters.
+    fn get_left_sizes(&self) -> Vec<usize> {
+        (self.frozen_left.len()..=LETTERS_COUNT - self.frozen_right.len())
+            .filter(|&x| self.fits(x, LETTERS_COUNT - x))
+            .collect()
+    }
+
+    /// Results of the previous run, `load` starts from them and `save` overwrites them.
+    fn get_path(&self) -> String {
+        format!("data/letters.{}", self.output_format.extension())
+    }
+
+    /// Rescores a saved split, `None` when it does not fit current settings.
+    fn restore(&self, record: &LettersRecord) -> Option<LettersPointer> {
+        let left = record.left.chars().collect_vec();
+        let right = record.right.chars().collect_vec();
+        let all = left
+            .iter()
+            .chain(right.iter())
+            .copied()
+            .sorted()
+            .collect_vec();
+
+        if all != ('a'..='z').collect_vec()
+            || !self.fits(left.len(), right.len())
+            || !self.frozen_left.iter().all(|x| left.contains(x))
+            || !self.frozen_right.iter().all(|x| right.contains(x))
+        {
+            return None;
+        }
+
+        let version = get_version();
+
+        Some(Letters::new(
+            version.clone(),
+            &left,
+            &right,
+            Vec::new(),
+            version,
+            left.clone(),
+            right.clone(),
+            &self.digraphs,
+            &self.balance_penalty,
+        ))
+    }
+}
+
+/// Markdown files, like `data/letters.md`, are searched for result lines.
+fn read_records(path: &Path) -> io::Result<Vec<LettersRecord>> {
+    let text = fs::read_to_string(path)?;
+
+    if path.extension().is_some_and(|x| x == "md") {
+        return Ok(text
+            .lines()
+            .filter_map(LettersRecord::from_legacy)
+            .collect());
+    }
+
+    parse_records(&text, LettersRecord::from_legacy)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
 }
 
 impl IBehaviour<Mutation, Letters> for Behaviour {
     fn new(settings: &CliSettings) -> Self {
-        let path = settings.digraphs.clone().unwrap();
-        let digraphs = Digraphs::load(&path).unwrap();
+        let sources = settings.digraphs.clone().unwrap();
+        let alphabet = ('a'..='z').collect_vec();
+        let digraphs = Digraphs::load_sources(&sources, &alphabet, settings.strict)
+            .unwrap()
+            .with_direction(settings.digraphs_direction);
         let context = Context::new(settings);
         let mut frozen_left = HashSet::with_capacity(settings.frozen_left.len());
         frozen_left.extend(settings.frozen_left.chars());
@@ -26,40 +114,48 @@ impl IBehaviour<Mutation, Letters> for Behaviour {
         let mut frozen_right = HashSet::with_capacity(settings.frozen_right.len());
         frozen_right.extend(settings.frozen_right.chars());
 
-        Behaviour {
+        let behaviour = Behaviour {
+            left_size: settings
+                .left_size
+                .unwrap_or_else(|| GroupSize::exact(context.left_count)),
+            right_size: settings.right_size.unwrap_or(GroupSize {
+                min: 0,
+                max: LETTERS_COUNT,
+            }),
             digraphs,
             context,
             frozen_left,
             frozen_right,
+            balance_penalty: settings.balance_penalty.unwrap_or(BalancePenalty::Letters),
+            output_format: settings.output_format,
+            seed: settings.seed.clone(),
+        };
+
+        if behaviour.get_left_sizes().is_empty() {
+            panic!("Group sizes do not fit the frozen letters or each other.")
         }
+
+        behaviour
     }
 
     fn generate(&self) -> LettersPointer {
-        let context = &self.context;
+        let mut rng = thread_rng();
+        let left_count = *self
+            .get_left_sizes()
+            .choose(&mut rng)
+            .expect("Group sizes do not fit.");
         let mut all = ('a'..='z')
             .filter(|&x| !self.frozen_right.contains(&x))
             .filter(|&x| !self.frozen_left.contains(&x))
             .collect_vec();
 
-        all.shuffle(&mut rand::thread_rng());
-
-        let mut left = self.frozen_left.iter().map(|&x| x).collect_vec();
-        left.append(
-            &mut all
-                .iter()
-                .take(context.left_count - left.len())
-                .map(|&x| x)
-                .collect(),
-        );
-
-        let mut right = self.frozen_right.iter().map(|&x| x).collect_vec();
-        right.append(
-            &mut all
-                .iter()
-                .filter(|x| !left.contains(x))
-                .map(|&x| x)
-                .collect(),
-        );
+        all.shuffle(&mut rng);
+
+        let mut left = self.frozen_left.iter().copied().collect_vec();
+        left.append(&mut all.iter().take(left_count - left.len()).copied().collect());
+
+        let mut right = self.frozen_right.iter().copied().collect_vec();
+        right.append(&mut all.iter().filter(|x| !left.contains(x)).copied().collect());
 
         let version = get_version();
 
@@ -72,11 +168,16 @@ impl IBehaviour<Mutation, Letters> for Behaviour {
             left.clone(),
             right.clone(),
             &self.digraphs,
+            &self.balance_penalty,
         )
     }
 
     fn calculate_score(&self, individual: &Letters) -> f64 {
-        calculate_score(individual.left_score, individual.right_score)
+        calculate_score(
+            individual.left_score,
+            individual.right_score,
+            &self.balance_penalty,
+        )
     }
 
     fn cross(&self, individual: &Letters, partner: &Letters) -> LettersPointer {
@@ -87,21 +188,30 @@ impl IBehaviour<Mutation, Letters> for Behaviour {
             .iter()
             .chain(partner.mutations.iter())
             .unique()
-            .map(|&x| x)
+            .copied()
             .collect();
 
         mutations.shuffle(&mut rand::thread_rng());
 
-        for mutation in mutations.iter().take(self.context.mutations_count) {
-            let left_index = left.iter().position(|&x| x == mutation.left);
-            let right_index = right.iter().position(|&x| x == mutation.right);
+        // mutations of different individuals can conflict or break group sizes,
+        // such ones are skipped, and like in `mutate` each letter changes its hand once.
+        let mut applied = Vec::with_capacity(self.context.mutations_count);
+        for mutation in mutations {
+            if applied.len() == self.context.mutations_count {
+                break;
+            }
+            if applied.iter().any(|x| mutation.overlaps(x)) {
+                continue;
+            }
 
-            match (left_index, right_index) {
-                (Some(left_index), Some(right_index)) => {
-                    left[left_index] = mutation.left;
-                    right[right_index] = mutation.right;
-                }
-                _ => panic!("Incompatible mutation!"),
+            let mut next_left = left.clone();
+            let mut next_right = right.clone();
+            if mutation.apply(&mut next_left, &mut next_right)
+                && self.fits(next_left.len(), next_right.len())
+            {
+                left = next_left;
+                right = next_right;
+                applied.push(mutation);
             }
         }
 
@@ -109,54 +219,75 @@ impl IBehaviour<Mutation, Letters> for Behaviour {
             get_version(),
             &left,
             &right,
-            mutations, // this mutations is not just a sum of 2 mutations, it's an intersection.
+            applied, // this mutations is not just a sum of 2 mutations, it's an intersection.
             individual.parent_version.clone(), // so, to be able to get the current state,
             individual.parent_left.clone(), // we have apply this mutations on the initial parent letters.
             individual.parent_right.clone(), // current - mutations = parent.
             &self.digraphs,
+            &self.balance_penalty,
         )
     }
 
     fn mutate(&self, individual: &Letters) -> LettersPointer {
         let mut rng = thread_rng();
-        let context = &self.context;
+        let mut left = individual.left.clone();
+        let mut right = individual.right.clone();
 
-        let mut left = individual
-            .left
+        // each letter changes its hand once, so mutations do not revert each other.
+        let mut free_left = left
             .iter()
             .filter(|&x| !self.frozen_left.contains(x))
-            .map(|&x| x)
+            .copied()
             .collect_vec();
-        left.shuffle(&mut rng);
+        free_left.shuffle(&mut rng);
 
-        let mut right = individual
-            .right
+        let mut free_right = right
             .iter()
             .filter(|&x| !self.frozen_right.contains(x))
-            .map(|&x| x)
+            .copied()
             .collect_vec();
-        right.shuffle(&mut rng);
+        free_right.shuffle(&mut rng);
 
-        let mut mutations: Vec<_> = Vec::with_capacity(context.mutations_count);
+        let mut mutations: Vec<_> = Vec::with_capacity(self.context.mutations_count);
 
-        let mutations_count = min(vec![context.mutations_count, left.len(), right.len()]).unwrap();
-
-        for index in 0..mutations_count {
-            let left_char = left[index];
-            let right_char = right[index];
+        for _ in 0..self.context.mutations_count {
+            let mut candidates = Vec::with_capacity(3);
+            if let (Some(&left_char), Some(&right_char)) = (free_left.last(), free_right.last()) {
+                candidates.push(Mutation::Swap {
+                    left: left_char,
+                    right: right_char,
+                });
+            }
+            match free_left.last() {
+                Some(&letter) if self.fits(left.len() - 1, right.len() + 1) => {
+                    candidates.push(Mutation::Move {
+                        letter,
+                        to_left: false,
+                    })
+                }
+                _ => {}
+            }
+            match free_right.last() {
+                Some(&letter) if self.fits(left.len() + 1, right.len() - 1) => {
+                    candidates.push(Mutation::Move {
+                        letter,
+                        to_left: true,
+                    })
+                }
+                _ => {}
+            }
 
-            left[index] = right_char;
-            right[index] = left_char;
+            let mutation = match candidates.choose(&mut rng) {
+                Some(&mutation) => mutation,
+                None => break,
+            };
 
-            mutations.push(Mutation {
-                left: left_char,
-                right: right_char,
-            });
+            mutation.apply(&mut left, &mut right);
+            free_left.retain(|&x| !mutation.contains(x));
+            free_right.retain(|&x| !mutation.contains(x));
+            mutations.push(mutation);
         }
 
-        left.extend(&self.frozen_left.iter().map(|&x| x).collect_vec());
-        right.extend(&self.frozen_right.iter().map(|&x| x).collect_vec());
-
         Letters::new(
             get_version(),
             &left,
@@ -166,18 +297,51 @@ impl IBehaviour<Mutation, Letters> for Behaviour {
             individual.left.clone(),
             individual.right.clone(),
             &self.digraphs,
+            &self.balance_penalty,
         )
     }
 
-    fn get_context<'a>(&'a self) -> &'a Context {
+    fn get_context(&self) -> &Context {
         &self.context
     }
 
     fn load(&self) -> std::io::Result<Vec<Box<Letters>>> {
-        Ok(Vec::new())
+        let mut records = Vec::new();
+
+        if let Some(seed) = &self.seed {
+            records.extend(read_records(seed)?);
+        }
+
+        let path = PathBuf::from(self.get_path());
+        if path.exists() {
+            records.extend(read_records(&path)?);
+        }
+
+        let letters = records
+            .iter()
+            .filter_map(|x| self.restore(x))
+            .unique()
+            .collect_vec();
+
+        if letters.len() < records.len() {
+            eprintln!(
+                "warning: {} of {} saved splits do not fit the settings or repeat.",
+                records.len() - letters.len(),
+                records.len()
+            );
+        }
+
+        Ok(letters)
     }
 
-    fn save(_: &Vec<Box<Letters>>) -> std::io::Result<()> {
+    fn save(&self, individuals: &[Box<Letters>]) -> std::io::Result<()> {
+        let records = individuals.iter().map(|x| x.to_record()).collect_vec();
+        let text = format_records(&records, self.output_format)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        let mut file = File::create(self.get_path())?;
+        file.write_all(text.as_bytes())?;
+        file.sync_all()?;
+
         Ok(())
     }
 }
@@ -201,17 +365,23 @@ pub mod tests {
         }
     }
 
-    #[test]
-    fn unique_should_work() {
-        let json = json!({});
-        let digraphs = Digraphs::new(&json.as_object().unwrap());
-        let context = default_context();
-        let behaviour = Behaviour {
-            digraphs,
-            context,
+    fn default_behaviour() -> Behaviour {
+        Behaviour {
+            digraphs: Digraphs::new(json!({}).as_object().unwrap()),
+            context: default_context(),
             frozen_right: HashSet::new(),
             frozen_left: HashSet::new(),
-        };
+            balance_penalty: BalancePenalty::Letters,
+            left_size: GroupSize::exact(15),
+            right_size: GroupSize::exact(11),
+            output_format: OutputFormat::Legacy,
+            seed: None,
+        }
+    }
+
+    #[test]
+    fn unique_should_work() {
+        let behaviour = default_behaviour();
         let a = behaviour.generate();
         let b = behaviour.generate();
         let clone = a.clone();
@@ -224,16 +394,8 @@ pub mod tests {
 
     #[test]
     fn should_assign_parent_version() {
-        let json = json!({});
-        let digraphs = Digraphs::new(&json.as_object().unwrap());
-        let mut context = default_context();
-        context.mutations_count = 1;
-        let behaviour = Behaviour {
-            digraphs,
-            context,
-            frozen_right: HashSet::new(),
-            frozen_left: HashSet::new(),
-        };
+        let mut behaviour = default_behaviour();
+        behaviour.context.mutations_count = 1;
 
         let target = behaviour.generate();
         let actual = behaviour.mutate(&target);
@@ -243,15 +405,7 @@ pub mod tests {
 
     #[test]
     fn should_not_mutate_source_object() {
-        let json = json!({});
-        let digraphs = Digraphs::new(&json.as_object().unwrap());
-        let context = default_context();
-        let behaviour = Behaviour {
-            digraphs,
-            context,
-            frozen_right: HashSet::new(),
-            frozen_left: HashSet::new(),
-        };
+        let behaviour = default_behaviour();
         let target = behaviour.generate();
         let copy = target.left.clone();
         let actual = behaviour.mutate(&target);
@@ -262,15 +416,7 @@ pub mod tests {
 
     #[test]
     fn should_mutate() {
-        let json = json!({});
-        let digraphs = Digraphs::new(&json.as_object().unwrap());
-        let context = default_context();
-        let behaviour = Behaviour {
-            digraphs,
-            context,
-            frozen_right: HashSet::new(),
-            frozen_left: HashSet::new(),
-        };
+        let behaviour = default_behaviour();
         let target = behaviour.generate();
 
         let actual = behaviour.mutate(&target);
@@ -281,15 +427,7 @@ pub mod tests {
 
     #[test]
     fn should_sort_chars() {
-        let json = json!({});
-        let digraphs = Digraphs::new(&json.as_object().unwrap());
-        let context = default_context();
-        let behaviour = Behaviour {
-            digraphs,
-            context,
-            frozen_right: HashSet::new(),
-            frozen_left: HashSet::new(),
-        };
+        let behaviour = default_behaviour();
         let letters = behaviour.generate();
 
         let target = to_sorted_string(&letters.left);
@@ -303,8 +441,93 @@ pub mod tests {
         assert_eq!(target, actual);
     }
 
-    fn to_sorted_string(list: &Vec<char>) -> String {
-        let mut vec = list.clone();
+    #[test]
+    fn should_keep_group_sizes_in_bounds() {
+        let mut behaviour = default_behaviour();
+        behaviour.frozen_left = ['s'].iter().copied().collect();
+        behaviour.left_size = GroupSize { min: 12, max: 14 };
+        behaviour.right_size = GroupSize { min: 0, max: 13 };
+        let mut sizes = HashSet::new();
+
+        for _ in 0..100 {
+            let parent = behaviour.generate();
+            let child = behaviour.mutate(&parent);
+            let partner = behaviour.mutate(&parent);
+            let crossed = behaviour.cross(&child, &partner);
+
+            for letters in [&parent, &child, &crossed] {
+                assert!((13..=14).contains(&letters.left.len()));
+                assert_eq!(letters.left.len() + letters.right.len(), 26);
+                assert!(letters.left.contains(&'s'));
+                sizes.insert(letters.left.len());
+            }
+        }
+
+        assert_eq!(sizes.len(), 2);
+    }
+
+    #[test]
+    fn should_not_undo_swaps_when_crossing() {
+        let mut behaviour = default_behaviour();
+        behaviour.left_size = GroupSize { min: 14, max: 15 };
+        behaviour.right_size = GroupSize { min: 11, max: 12 };
+        let left = ('a'..='o').collect_vec();
+        let right = ('p'..='z').collect_vec();
+        let create = |mutation: Mutation| {
+            let mut next_left = left.clone();
+            let mut next_right = right.clone();
+            mutation.apply(&mut next_left, &mut next_right);
+            Letters::new(
+                get_version(),
+                &next_left,
+                &next_right,
+                vec![mutation],
+                String::new(),
+                left.clone(),
+                right.clone(),
+                &behaviour.digraphs,
+                &behaviour.balance_penalty,
+            )
+        };
+        let individual = create(Mutation::Swap {
+            left: 'a',
+            right: 'p',
+        });
+        let partner = create(Mutation::Move {
+            letter: 'a',
+            to_left: false,
+        });
+
+        for _ in 0..10 {
+            let actual = behaviour.cross(&individual, &partner);
+
+            assert_eq!(actual.mutations.len(), 1);
+            assert!(actual == individual || actual == partner);
+        }
+    }
+
+    #[test]
+    fn should_seed_from_markdown() {
+        let mut behaviour = default_behaviour();
+        behaviour.digraphs = Digraphs::new(json!({ "ab": 1.0 }).as_object().unwrap());
+        behaviour.frozen_left = ['s', 'e', 'r'].iter().copied().collect();
+        behaviour.seed = Some(PathBuf::from("data/letters.md"));
+
+        let records = read_records(Path::new("data/letters.md")).unwrap();
+        let actual = records
+            .iter()
+            .filter_map(|x| behaviour.restore(x))
+            .collect_vec();
+
+        assert_eq!(records.len(), 10);
+        // one of "zxcvas" splits and "all" ones do not have "s", "e" and "r" on the left.
+        assert_eq!(actual.len(), 7);
+        assert_eq!(to_sorted_string(&actual[0].left), "abcejkmpqrsuvxz");
+        assert_eq!(actual[0].left_score, 1.);
+    }
+
+    fn to_sorted_string(list: &[char]) -> String {
+        let mut vec = list.to_vec();
         vec.sort();
         vec.iter().collect()
     }
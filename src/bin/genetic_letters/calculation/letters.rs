@@ -1,16 +1,72 @@
-use ed_balance::{format_result, Digraphs, IIndividual, IMutation};
+use ed_balance::{BalancePenalty, Digraphs, IIndividual, IMutation, LettersRecord, ResultRecord};
 use std::hash::Hash;
 
 pub type LettersPointer = Box<Letters>;
 
-#[derive(Hash, Eq, PartialEq, Clone, Copy)]
-pub struct Mutation {
-    pub left: char,
-    pub right: char,
+#[derive(Debug, Hash, Eq, PartialEq, Clone, Copy)]
+pub enum Mutation {
+    /// Letters exchange hands.
+    Swap { left: char, right: char },
+
+    /// A letter goes to the other hand, so group sizes change.
+    Move { letter: char, to_left: bool },
 }
 
 impl IMutation for Mutation {}
 
+impl Mutation {
+    pub fn contains(&self, letter: char) -> bool {
+        match *self {
+            Mutation::Swap { left, right } => left == letter || right == letter,
+            Mutation::Move { letter: x, .. } => x == letter,
+        }
+    }
+
+    pub fn overlaps(&self, other: &Mutation) -> bool {
+        match *other {
+            Mutation::Swap { left, right } => self.contains(left) || self.contains(right),
+            Mutation::Move { letter, .. } => self.contains(letter),
+        }
+    }
+
+    /// Returns `false` and keeps letters as is when the mutation does not fit them.
+    pub fn apply(&self, left: &mut Vec<char>, right: &mut Vec<char>) -> bool {
+        match *self {
+            Mutation::Swap {
+                left: left_char,
+                right: right_char,
+            } => {
+                let left_index = left.iter().position(|&x| x == left_char);
+                let right_index = right.iter().position(|&x| x == right_char);
+
+                match (left_index, right_index) {
+                    (Some(left_index), Some(right_index)) => {
+                        left[left_index] = right_char;
+                        right[right_index] = left_char;
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            Mutation::Move { letter, to_left } => {
+                let (from, to) = if to_left {
+                    (right, left)
+                } else {
+                    (left, right)
+                };
+
+                match from.iter().position(|&x| x == letter) {
+                    Some(index) => {
+                        to.push(from.remove(index));
+                        true
+                    }
+                    None => false,
+                }
+            }
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Letters {
     pub version: String,
@@ -18,6 +74,12 @@ pub struct Letters {
     pub right: Vec<char>,
     pub left_score: f64,
     pub right_score: f64,
+
+    /// The balance curve of left and right scores.
+    pub penalty: BalancePenalty,
+
+    /// Left and right scores for each blended digraphs source.
+    pub components: Vec<(f64, f64)>,
     pub mutations: Vec<Mutation>,
     pub parent_version: String,
     pub parent_left: Vec<char>,
@@ -30,7 +92,7 @@ impl IIndividual<Mutation> for Letters {
     }
 
     fn to_string(&self) -> String {
-        format_result(&self.left, &self.right, self.left_score, self.right_score)
+        self.to_record().to_legacy()
     }
 
     fn get_score(&self) -> f64 {
@@ -39,29 +101,45 @@ impl IIndividual<Mutation> for Letters {
 }
 
 impl Letters {
+    pub fn to_record(&self) -> LettersRecord {
+        LettersRecord::new(
+            &self.left,
+            &self.right,
+            self.left_score,
+            self.right_score,
+            &self.penalty,
+            &self.components,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         version: String,
-        left: &Vec<char>,
-        right: &Vec<char>,
+        left: &[char],
+        right: &[char],
         mutations: Vec<Mutation>,
         parent_version: String,
         parent_left: Vec<char>,
         parent_right: Vec<char>,
         digraphs: &Digraphs,
+        penalty: &BalancePenalty,
     ) -> LettersPointer {
-        let mut sorted_left = left.clone();
-        let mut sorted_right = right.clone();
+        let mut sorted_left = left.to_vec();
+        let mut sorted_right = right.to_vec();
         sorted_left.sort();
         sorted_right.sort();
 
         let left_score = digraphs.calculate_score(&sorted_left);
         let right_score = digraphs.calculate_score(&sorted_right);
+        let components = digraphs.calculate_component_scores(&sorted_left, &sorted_right);
 
         box_letters(Letters {
             left: sorted_left,
             right: sorted_right,
             left_score,
             right_score,
+            penalty: *penalty,
+            components,
             version,
             mutations,
             parent_version,
@@ -92,3 +170,52 @@ impl Hash for Letters {
 fn box_letters(letters: Letters) -> LettersPointer {
     Box::new(letters)
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn should_apply_mutations() {
+        let mut left = vec!['a', 'b'];
+        let mut right = vec!['c'];
+
+        assert!(Mutation::Swap {
+            left: 'a',
+            right: 'c'
+        }
+        .apply(&mut left, &mut right));
+        assert_eq!((&left, &right), (&vec!['c', 'b'], &vec!['a']));
+
+        assert!(Mutation::Move {
+            letter: 'b',
+            to_left: false
+        }
+        .apply(&mut left, &mut right));
+        assert_eq!((&left, &right), (&vec!['c'], &vec!['a', 'b']));
+
+        assert!(Mutation::Move {
+            letter: 'a',
+            to_left: true
+        }
+        .apply(&mut left, &mut right));
+        assert_eq!((&left, &right), (&vec!['c', 'a'], &vec!['b']));
+
+        assert!(!Mutation::Swap {
+            left: 'b',
+            right: 'c'
+        }
+        .apply(&mut left, &mut right));
+        assert!(!Mutation::Move {
+            letter: 'a',
+            to_left: true
+        }
+        .apply(&mut left, &mut right));
+        assert!(!Mutation::Move {
+            letter: 'z',
+            to_left: false
+        }
+        .apply(&mut left, &mut right));
+        assert_eq!((&left, &right), (&vec!['c', 'a'], &vec!['b']));
+    }
+}
@@ -2,7 +2,7 @@ use crate::{Context, IBehaviour, IIndividual, IMutation};
 use itertools::Itertools;
 use rand::{thread_rng, RngCore};
 use rayon::prelude::*;
-use std::marker::PhantomData;
+use std::{cmp::Ordering, marker::PhantomData};
 
 pub struct GeneticAlgorithm<'a, TMutation, TIndividual, TBehaviour>
 where
@@ -32,9 +32,9 @@ where
 
     pub fn run(
         &self,
-        population: &Vec<Box<TIndividual>>,
+        population: &[Box<TIndividual>],
         context: &Context,
-    ) -> Result<Vec<Box<TIndividual>>, ()> {
+    ) -> Option<Vec<Box<TIndividual>>> {
         let best_population_size = context.population_size / 2;
 
         let sorted = population
@@ -59,15 +59,10 @@ where
 
         let mut rest = self.process(&mut rest, context.population_size, context.children_count)?;
 
-        let top_rest = rest[0].get_score();
-
-        let mut best = best
-            .into_iter()
-            .filter(|x| x.get_score() < top_rest)
-            .collect_vec();
+        let mut best = keep_better(best, &rest[0], |a, b| self.behaviour.score_cmp(a, b));
         best.append(&mut rest);
 
-        Ok(best)
+        Some(best)
     }
 
     fn process(
@@ -75,7 +70,7 @@ where
         population: &mut Vec<Box<TIndividual>>,
         population_size: usize,
         children_count: u32,
-    ) -> Result<Vec<Box<TIndividual>>, ()> {
+    ) -> Option<Vec<Box<TIndividual>>> {
         let mut rng = thread_rng();
         let max_children_count = 1 + (rng.next_u32() % children_count);
         let mut mutants: Vec<_> = population
@@ -103,15 +98,14 @@ where
             .into_iter()
             .unique()
             .sorted_by(|a, b| self.behaviour.score_cmp(a, b))
-            .into_iter()
             .take(population_size)
             .collect();
 
-        if offspring.len() == 0 {
-            return Err(());
+        if offspring.is_empty() {
+            return None;
         }
 
-        Ok(offspring)
+        Some(offspring)
     }
 
     fn recombine(&self, collection: Vec<Box<TIndividual>>) -> Vec<Box<TIndividual>> {
@@ -122,10 +116,33 @@ where
         let mut crossed = collection
             .iter()
             .tuple_windows()
-            .map(|(a, b)| self.behaviour.cross(&a, &b))
+            .map(|(a, b)| self.behaviour.cross(a, b))
             .collect_vec();
 
         crossed.extend(collection);
         crossed
     }
 }
+
+/// Individuals which are better than the top of the rest,
+/// scores can be better when higher or lower, so behaviours compare them.
+fn keep_better<T>(best: Vec<T>, top_rest: &T, cmp: impl Fn(&T, &T) -> Ordering) -> Vec<T> {
+    best.into_iter()
+        .filter(|x| cmp(x, top_rest) == Ordering::Less)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_keep_better_by_score_cmp() {
+        let higher = |a: &f64, b: &f64| b.partial_cmp(a).unwrap();
+        let lower = |a: &f64, b: &f64| a.partial_cmp(b).unwrap();
+
+        assert_eq!(keep_better(vec![5., 3., 1.], &2., higher), vec![5., 3.]);
+        assert_eq!(keep_better(vec![5., 3., 1.], &2., lower), vec![1.]);
+        assert_eq!(keep_better(vec![2.], &2., higher), Vec::<f64>::new());
+    }
+}
@@ -1,7 +1,7 @@
+mod algorithm;
 mod models;
 mod process;
-mod algorithm;
 
-pub use models::*;
 pub use algorithm::*;
+pub use models::*;
 pub use process::*;
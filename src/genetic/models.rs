@@ -19,7 +19,7 @@ pub trait IBehaviour<TMutation: IMutation, TIndividual: IIndividual<TMutation>>:
 
     fn load(&self) -> std::io::Result<Vec<Box<TIndividual>>>;
 
-    fn save(individuals: &Vec<Box<TIndividual>>) -> std::io::Result<()>;
+    fn save(&self, individuals: &[Box<TIndividual>]) -> std::io::Result<()>;
 
     fn generate(&self) -> Box<TIndividual>;
 
@@ -29,7 +29,7 @@ pub trait IBehaviour<TMutation: IMutation, TIndividual: IIndividual<TMutation>>:
 
     fn mutate(&self, individual: &TIndividual) -> Box<TIndividual>;
 
-    fn get_context<'a>(&'a self) -> &'a Context;
+    fn get_context(&self) -> &Context;
 
     fn score_cmp(&self, a: &TIndividual, b: &TIndividual) -> Ordering {
         let a_total = self.calculate_score(a);
@@ -51,7 +51,7 @@ pub struct Context {
 
 impl Context {
     pub fn new(settings: &CliSettings) -> Self {
-        if settings.mutations_count <= 0 {
+        if settings.mutations_count == 0 {
             panic!("Invalid mutations count.")
         }
 
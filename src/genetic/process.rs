@@ -1,4 +1,4 @@
-use crate::{CliSettings, Context, DynError, GeneticAlgorithm, IBehaviour, IIndividual, IMutation};
+use crate::{CliSettings, DynError, GeneticAlgorithm, IBehaviour, IIndividual, IMutation};
 use chrono::prelude::*;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use itertools::Itertools;
@@ -28,20 +28,19 @@ where
         .collect();
 
     let settings = Arc::clone(&settings);
-    let _ = thread::spawn(move || {
+    let handle = thread::spawn(move || {
         let behaviour = TBehaviour::new(&settings);
         let context = behaviour.get_context();
         let algorithm = GeneticAlgorithm::new(&behaviour);
 
         let mut population: Vec<_> = (0..context.population_size)
-            .into_iter()
             .map(|_| behaviour.generate())
             .collect();
         population.extend(behaviour.load().unwrap());
 
         // to be able just calculate scores
         if context.generations_count == 0 {
-            TBehaviour::save(&population).unwrap();
+            behaviour.save(&population).unwrap();
             return;
         }
 
@@ -50,7 +49,7 @@ where
         let mut repeats_counter = 0;
 
         for index in 0..context.generations_count {
-            population = algorithm.run(&population, &context).expect("All died!");
+            population = algorithm.run(&population, context).expect("All died!");
 
             let (repeats, top_results, to_continue) = need_to_continue(
                 repeats_counter,
@@ -75,7 +74,7 @@ where
                 &pb_main,
                 &progress_bars,
                 &population,
-                context,
+                &behaviour,
                 repeats_counter,
             ) {
                 prev = date;
@@ -88,7 +87,7 @@ where
             &pb_main,
             &progress_bars,
             &population,
-            context,
+            &behaviour,
             repeats_counter,
         );
         pb_main.finish();
@@ -96,6 +95,7 @@ where
     });
 
     progress.join()?;
+    handle.join().map_err(|_| "Calculations thread panicked.")?;
 
     Ok(())
 }
@@ -103,7 +103,7 @@ where
 fn need_to_continue<TMutation, TIndividual, TBehaviour>(
     mut repeats_counter: u8,
     prev_result: &Vec<Box<TIndividual>>,
-    population: &Vec<Box<TIndividual>>,
+    population: &[Box<TIndividual>],
     results_count: usize,
     max_repeats_count: u8,
     behaviour: &TBehaviour,
@@ -141,9 +141,9 @@ fn render_progress<TMutation, TIndividual, TBehaviour>(
     index: u32,
     prev: DateTime<Utc>,
     pb_main: &ProgressBar,
-    progress_bars: &Vec<ProgressBar>,
-    population: &Vec<Box<TIndividual>>,
-    context: &Context,
+    progress_bars: &[ProgressBar],
+    population: &[Box<TIndividual>],
+    behaviour: &TBehaviour,
     repeats_counter: u8,
 ) -> Option<DateTime<Utc>>
 where
@@ -151,6 +151,7 @@ where
     TMutation: IMutation,
     TBehaviour: IBehaviour<TMutation, TIndividual>,
 {
+    let context = behaviour.get_context();
     let passed = Utc::now() - prev;
 
     if passed.num_seconds() >= 5 || index == 0 || index == context.generations_count - 1 {
@@ -162,14 +163,15 @@ where
         }
 
         pb_main.set_position(index as u64);
-        TBehaviour::save(
-            &population
-                .iter()
-                .take(context.population_size)
-                .cloned()
-                .collect_vec(),
-        )
-        .unwrap();
+        behaviour
+            .save(
+                &population
+                    .iter()
+                    .take(context.population_size)
+                    .cloned()
+                    .collect_vec(),
+            )
+            .unwrap();
 
         return Some(Utc::now());
     }
@@ -0,0 +1,229 @@
+use std::{fmt, str::FromStr};
+
+/// How much a score suffers from a bad balance between hands.\
+/// Curves return a penalty that is `0` for the ideal balance and grows when it gets worse.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BalancePenalty {
+    /// `1 - (1.1 - 0.1 / ratio)^3`, the original curve of the letters optimizers.
+    Letters,
+
+    /// `2 - 2 / ((1 / ratio - 1)^2 + 1)`, the original curve of the keyboard optimizer.
+    Keyboard,
+
+    /// `slope * (1 - ratio)`
+    Linear { slope: f64 },
+
+    /// `slope * (1 - ratio)^2`
+    Quadratic { slope: f64 },
+
+    /// `e^(slope * (1 - ratio)) - 1`
+    Exponential { slope: f64 },
+
+    /// `slope * (1 - ratio - tolerance)`, no penalty inside the tolerance band.
+    Hinge { tolerance: f64, slope: f64 },
+
+    /// Linear penalty for the distance to the desired `left / right` ratio.
+    Target { ratio: f64, slope: f64 },
+}
+
+impl BalancePenalty {
+    pub fn penalty(&self, left: f64, right: f64) -> f64 {
+        let ratio = get_ratio(left, right);
+
+        match *self {
+            BalancePenalty::Letters => 1. - get_letters_factor(ratio),
+            BalancePenalty::Keyboard => get_keyboard_factor(left, right) - 1.,
+            BalancePenalty::Linear { slope } => slope * (1. - ratio),
+            BalancePenalty::Quadratic { slope } => slope * (1. - ratio).powi(2),
+            BalancePenalty::Exponential { slope } => (slope * (1. - ratio)).exp() - 1.,
+            BalancePenalty::Hinge { tolerance, slope } => slope * (1. - ratio - tolerance).max(0.),
+            BalancePenalty::Target { ratio, slope } => {
+                slope * (1. - get_ratio(left / right, ratio))
+            }
+        }
+    }
+
+    /// A multiplier for scores where higher is better.\
+    /// `1` for the ideal balance, lower for worse ones.
+    pub fn score_factor(&self, left: f64, right: f64) -> f64 {
+        match self {
+            // the original formula is kept as is to have exactly the same scores.
+            BalancePenalty::Letters => get_letters_factor(get_ratio(left, right)),
+            _ => 1. - self.penalty(left, right),
+        }
+    }
+
+    /// The `factor` column of results.\
+    /// `1.1 - 0.1 / ratio` for `letters`, like in old results where the score uses its cube,
+    /// the score factor for other curves.
+    pub fn balance_factor(&self, left: f64, right: f64) -> f64 {
+        match self {
+            BalancePenalty::Letters => get_letters_base(get_ratio(left, right)),
+            _ => self.score_factor(left, right),
+        }
+    }
+
+    /// A multiplier for efforts where lower is better.\
+    /// `1` for the ideal balance, bigger for worse ones.
+    pub fn effort_factor(&self, left: f64, right: f64) -> f64 {
+        match self {
+            BalancePenalty::Keyboard => get_keyboard_factor(left, right),
+            _ => 1. + self.penalty(left, right),
+        }
+    }
+}
+
+fn get_letters_factor(ratio: f64) -> f64 {
+    let factor = get_letters_base(ratio);
+
+    factor * factor * factor
+}
+
+fn get_letters_base(ratio: f64) -> f64 {
+    1.1 - 0.1 / ratio
+}
+
+fn get_keyboard_factor(left: f64, right: f64) -> f64 {
+    // the same as the formula gives, but without `0 / 0` for empty hands.
+    if left == right {
+        return 1.;
+    }
+
+    let ballance = if left > right {
+        left / right
+    } else {
+        right / left
+    };
+
+    // https://www.desmos.com/calculator
+    // bigger power - less strict ballance
+    3. - (2. / ((ballance - 1.).powi(2) + 1.))
+}
+
+/// `min / max`, so `1` is the ideal balance.
+fn get_ratio(left: f64, right: f64) -> f64 {
+    if left < right {
+        left / right
+    } else {
+        right / left
+    }
+}
+
+/// `letters`, `keyboard`, `linear:slope`, `quadratic:slope`, `exponential:slope`,
+/// `hinge:tolerance:slope` or `target:ratio:slope`.
+impl FromStr for BalancePenalty {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<_> = value.split(':').map(|x| x.trim()).collect();
+        let numbers = parts[1..]
+            .iter()
+            .map(|x| {
+                x.parse::<f64>()
+                    .map_err(|_| format!("'{}' is not a number in '{}'.", x, value))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let penalty = match (parts[0], numbers.as_slice()) {
+            ("letters", []) => BalancePenalty::Letters,
+            ("keyboard", []) => BalancePenalty::Keyboard,
+            ("linear", &[slope]) => BalancePenalty::Linear { slope },
+            ("quadratic", &[slope]) => BalancePenalty::Quadratic { slope },
+            ("exponential", &[slope]) => BalancePenalty::Exponential { slope },
+            ("hinge", &[tolerance, slope]) => BalancePenalty::Hinge { tolerance, slope },
+            ("target", &[ratio, slope]) if ratio > 0. => BalancePenalty::Target { ratio, slope },
+            _ => {
+                return Err(format!(
+                    "'{}' is not a balance penalty, expected one of: letters, keyboard, \
+                    linear:slope, quadratic:slope, exponential:slope, \
+                    hinge:tolerance:slope, target:ratio:slope.",
+                    value
+                ))
+            }
+        };
+
+        Ok(penalty)
+    }
+}
+
+impl fmt::Display for BalancePenalty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BalancePenalty::Letters => write!(f, "letters"),
+            BalancePenalty::Keyboard => write!(f, "keyboard"),
+            BalancePenalty::Linear { slope } => write!(f, "linear:{}", slope),
+            BalancePenalty::Quadratic { slope } => write!(f, "quadratic:{}", slope),
+            BalancePenalty::Exponential { slope } => write!(f, "exponential:{}", slope),
+            BalancePenalty::Hinge { tolerance, slope } => {
+                write!(f, "hinge:{}:{}", tolerance, slope)
+            }
+            BalancePenalty::Target { ratio, slope } => write!(f, "target:{}:{}", ratio, slope),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_curves_should_match_original_formulas() {
+        let (left, right) = (27.723, 26.999);
+        let factor = 1.1 - 0.1 / (right / left);
+        let balance: f64 = left / right;
+
+        assert_eq!(
+            BalancePenalty::Letters.score_factor(left, right),
+            factor * factor * factor
+        );
+        assert_eq!(BalancePenalty::Letters.balance_factor(left, right), factor);
+        assert_eq!(
+            BalancePenalty::Keyboard.effort_factor(left, right),
+            3. - (2. / ((balance - 1.).powi(2) + 1.))
+        );
+    }
+
+    #[test]
+    fn ideal_balance_should_not_be_penalized() {
+        for penalty in [
+            "letters",
+            "keyboard",
+            "linear:2",
+            "quadratic:2",
+            "exponential:2",
+            "hinge:0.1:2",
+        ] {
+            let penalty: BalancePenalty = penalty.parse().unwrap();
+            assert_eq!(penalty.penalty(3., 3.), 0., "{}", penalty);
+        }
+
+        assert_eq!(BalancePenalty::Keyboard.effort_factor(0., 0.), 1.);
+
+        let target: BalancePenalty = "target:1.5:2".parse().unwrap();
+        assert_eq!(target.penalty(3., 2.), 0.);
+        assert!(target.penalty(3., 3.) > 0.);
+    }
+
+    #[test]
+    fn hinge_should_ignore_tolerance_band() {
+        let target = BalancePenalty::Hinge {
+            tolerance: 0.1,
+            slope: 2.,
+        };
+
+        assert_eq!(target.penalty(9.5, 10.), 0.);
+        assert!((target.penalty(8., 10.) - 2. * (0.2 - 0.1)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn should_parse_and_render() {
+        for value in ["letters", "linear:2", "hinge:0.1:2", "target:1.2:1"] {
+            let penalty: BalancePenalty = value.parse().unwrap();
+            assert_eq!(penalty.to_string(), value);
+        }
+
+        assert!("linear".parse::<BalancePenalty>().is_err());
+        assert!("cubic:1".parse::<BalancePenalty>().is_err());
+        assert!("target:0:1".parse::<BalancePenalty>().is_err());
+    }
+}
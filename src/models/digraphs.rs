@@ -1,3 +1,8 @@
+use super::{
+    report_issues, DigraphIssue, DigraphsDirection, DigraphsSources, ASYMMETRY_FLOOR,
+    ASYMMETRY_RATIO,
+};
+use itertools::Itertools;
 use serde_json::{Map, Value};
 use std::collections::HashMap;
 use std::error::Error;
@@ -6,34 +11,175 @@ use std::path::PathBuf;
 #[derive(Debug)]
 pub struct Digraphs {
     map: DigraphsMap,
+
+    /// Normalized tables of a blend in the order of the sources.\
+    /// Empty when digraphs are loaded from a single file.
+    components: Vec<Digraphs>,
+    direction: DigraphsDirection,
 }
 
+/// Each blended source is scaled to this total, so the blend is in percents.
+const NORMALIZED_TOTAL: f64 = 100.;
+
 pub type DigraphsMap = HashMap<char, HashMap<char, f64>>;
 
 impl Digraphs {
+    /// Skips invalid entries silently, use `parse` to get the list of issues.
     pub fn new(json: &Map<String, Value>) -> Digraphs {
-        let map = json
+        let (digraphs, _) = Digraphs::parse(json, &[]);
+
+        digraphs
+    }
+
+    /// Builds digraphs from valid entries and reports the rest.\
+    /// Keys with negative values are kept, malformed keys and non-numeric values are skipped.
+    pub fn parse(json: &Map<String, Value>, alphabet: &[char]) -> (Digraphs, Vec<DigraphIssue>) {
+        let mut issues = Vec::new();
+        let mut map = DigraphsMap::new();
+
+        for (digraph, value) in json.iter() {
+            let chars = digraph.chars().collect_vec();
+            if chars.len() != 2 {
+                issues.push(DigraphIssue::MalformedKey(digraph.clone()));
+                continue;
+            }
+
+            let value = match value.as_f64() {
+                Some(value) => value,
+                None => {
+                    issues.push(DigraphIssue::InvalidValue(
+                        digraph.clone(),
+                        value.to_string(),
+                    ));
+                    continue;
+                }
+            };
+
+            if value < 0. {
+                issues.push(DigraphIssue::NegativeValue(digraph.clone(), value));
+            }
+
+            map.entry(chars[0]).or_default().insert(chars[1], value);
+        }
+
+        json.keys()
+            .filter(|x| x.chars().count() == 2)
+            .into_group_map_by(|x| x.to_lowercase())
+            .into_iter()
+            .filter(|(_, keys)| keys.len() > 1)
+            .for_each(|(_, keys)| {
+                keys.iter().sorted().tuple_windows().for_each(|(a, b)| {
+                    issues.push(DigraphIssue::CaseDuplicate(a.to_string(), b.to_string()))
+                })
+            });
+
+        let digraphs = Digraphs {
+            map,
+            components: Vec::new(),
+            direction: DigraphsDirection::Both,
+        };
+
+        for &letter in alphabet {
+            if !digraphs.contains_letter(letter) {
+                issues.push(DigraphIssue::MissingLetter(letter));
+            }
+        }
+
+        for (&first, inner) in digraphs.map.iter().sorted_by_key(|(&x, _)| x) {
+            for (&second, &straight) in inner
+                .iter()
+                .filter(|(&second, _)| first < second)
+                .sorted_by_key(|(&x, _)| x)
+            {
+                let reverted = digraphs.get_value(&second, &first);
+                if straight.min(reverted) < ASYMMETRY_FLOOR {
+                    continue;
+                }
+
+                if straight / reverted > ASYMMETRY_RATIO || reverted / straight > ASYMMETRY_RATIO {
+                    issues.push(DigraphIssue::AsymmetricOutlier(
+                        format!("{}{}", first, second),
+                        straight,
+                        reverted,
+                    ));
+                }
+            }
+        }
+
+        (digraphs, issues)
+    }
+
+    /// Builds a weighted table where every source has the same total before weighting.\
+    /// Weights are normalized too, so only their ratio matters.
+    pub fn blend(sources: Vec<(Digraphs, f64)>) -> Digraphs {
+        let total_weight: f64 = sources.iter().map(|(_, weight)| weight).sum();
+        let mut map = DigraphsMap::new();
+        let mut components = Vec::with_capacity(sources.len());
+
+        for (digraphs, weight) in sources {
+            let component = digraphs.normalize();
+            for (&first, inner) in component.map.iter() {
+                for (&second, &value) in inner.iter() {
+                    *map.entry(first).or_default().entry(second).or_default() +=
+                        value * weight / total_weight;
+                }
+            }
+            components.push(component);
+        }
+
+        Digraphs {
+            map,
+            components,
+            direction: DigraphsDirection::Both,
+        }
+    }
+
+    /// A single source is used as is with its own total,
+    /// several ones are blended into a table of the normalized total.
+    pub fn load_sources(
+        sources: &DigraphsSources,
+        alphabet: &[char],
+        strict: bool,
+    ) -> Result<Digraphs, Box<dyn Error>> {
+        if let [source] = sources.0.as_slice() {
+            return Digraphs::load(&source.path, alphabet, strict);
+        }
+
+        let digraphs = sources
+            .0
             .iter()
-            .map(|(digraph, value)| {
-                let first = digraph.chars().nth(0)?;
-                let second = digraph.chars().nth(1)?;
-                let third = value.as_f64()?;
-                Some((first, second, third))
+            .map(|source| {
+                let digraphs = Digraphs::load(&source.path, alphabet, strict)?;
+                Ok((digraphs, source.weight))
             })
-            .map(|some| some.unwrap())
-            .fold(DigraphsMap::new(), |mut result, (first, second, value)| {
-                result
-                    .entry(first)
-                    .or_insert(HashMap::new())
-                    .insert(second, value);
-                result
-            });
+            .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
 
-        Digraphs { map }
+        Ok(Digraphs::blend(digraphs))
     }
 
-    pub fn calculate_score(&self, letters: &Vec<char>) -> f64 {
-        if letters.len() == 0 {
+    /// Applies to the blended sources too.
+    pub fn with_direction(self, direction: DigraphsDirection) -> Digraphs {
+        Digraphs {
+            map: self.map,
+            components: self
+                .components
+                .into_iter()
+                .map(|x| x.with_direction(direction))
+                .collect(),
+            direction,
+        }
+    }
+
+    /// Left and right scores for each blended source separately.
+    pub fn calculate_component_scores(&self, left: &[char], right: &[char]) -> Vec<(f64, f64)> {
+        self.components
+            .iter()
+            .map(|x| (x.calculate_score(left), x.calculate_score(right)))
+            .collect()
+    }
+
+    pub fn calculate_score(&self, letters: &[char]) -> f64 {
+        if letters.is_empty() {
             return 0.;
         }
 
@@ -46,8 +192,7 @@ impl Digraphs {
 
             while i < letters.len() {
                 let second = letters[i];
-                score += self.get_value(&first, &second);
-                score += self.get_value(&second, &first);
+                score += self.get_pair_score(first, second);
                 i += 1;
             }
             j += 1;
@@ -56,12 +201,62 @@ impl Digraphs {
         score
     }
 
-    pub fn load(path: &PathBuf) -> Result<Digraphs, Box<dyn Error>> {
+    /// Validates the file against the alphabet of the caller, like letters of a split.\
+    /// In the strict mode any invalid entry fails loading, otherwise it's just a warning.
+    pub fn load(
+        path: &PathBuf,
+        alphabet: &[char],
+        strict: bool,
+    ) -> Result<Digraphs, Box<dyn Error>> {
         let content = std::fs::read_to_string(path)?;
         let json: serde_json::Value = serde_json::from_str(&content)?;
-        let digraphs = json.as_object().unwrap();
+        let digraphs = json
+            .as_object()
+            .ok_or_else(|| format!("{} must contain a JSON object.", path.display()))?;
 
-        Ok(Digraphs::new(digraphs))
+        let (digraphs, issues) = Digraphs::parse(digraphs, alphabet);
+        report_issues(path, &issues, strict)?;
+
+        Ok(digraphs)
+    }
+
+    /// Both directions of the pair combined according to the direction mode.
+    pub fn get_pair_score(&self, first: char, second: char) -> f64 {
+        self.direction.combine(
+            self.get_value(&first, &second),
+            self.get_value(&second, &first),
+        )
+    }
+
+    fn normalize(self) -> Digraphs {
+        let total: f64 = self.map.values().flat_map(|x| x.values()).sum();
+        if total == 0. {
+            return self;
+        }
+
+        let map = self
+            .map
+            .into_iter()
+            .map(|(first, inner)| {
+                let inner = inner
+                    .into_iter()
+                    .map(|(second, value)| (second, value * NORMALIZED_TOTAL / total))
+                    .collect();
+                (first, inner)
+            })
+            .collect();
+
+        Digraphs {
+            map,
+            components: Vec::new(),
+            direction: DigraphsDirection::Both,
+        }
+    }
+
+    fn contains_letter(&self, letter: char) -> bool {
+        self.map
+            .iter()
+            .any(|(&first, inner)| first == letter || inner.contains_key(&letter))
     }
 
     fn get_value(&self, first: &char, second: &char) -> f64 {
@@ -87,7 +282,7 @@ pub mod tests {
             "dc": 5.0, // ony reverted
             "xz": 6.0, // not used
         });
-        let target = Digraphs::new(&json.as_object().unwrap());
+        let target = Digraphs::new(json.as_object().unwrap());
         let letters = vec!['b', 'c', 'd', 'f', 'g', 'a'];
         let actual = target.calculate_score(&letters);
 
@@ -98,26 +293,120 @@ pub mod tests {
         );
     }
 
+    #[test]
+    pub fn calculate_score_with_dominant_direction() {
+        let json = json!({
+            "ab": 4.0,
+            "ba": 1.0,
+            "cd": 2.0,
+            "dc": 2.0,
+        });
+        let direction = DigraphsDirection::Dominant {
+            dominant: 1.5,
+            reverse: 0.5,
+        };
+        let target = Digraphs::new(json.as_object().unwrap()).with_direction(direction);
+
+        assert_eq!(target.calculate_score(&['b', 'a']), 4. * 1.5 + 1. * 0.5);
+        assert_eq!(target.calculate_score(&['c', 'd']), 2. * 1.5 + 2. * 0.5);
+    }
+
     #[test]
     pub fn calculate_score_on_empty_vector() {
         let json = json!({
             "ab": 1.0,
             "bc": 2.0,
         });
-        let target = Digraphs::new(&json.as_object().unwrap());
+        let target = Digraphs::new(json.as_object().unwrap());
         let letters = Vec::with_capacity(0);
         let actual = target.calculate_score(&letters);
 
         assert_eq!(actual, 0.);
     }
 
+    #[test]
+    pub fn default_digraphs_should_not_have_issues() {
+        let content = std::fs::read_to_string("data/digraphs.json").unwrap();
+        let json: Value = serde_json::from_str(&content).unwrap();
+        let alphabet = ('a'..='z').collect_vec();
+
+        let (_, issues) = Digraphs::parse(json.as_object().unwrap(), &alphabet);
+
+        assert_eq!(issues, Vec::new());
+    }
+
+    #[test]
+    pub fn parse_should_report_invalid_entries() {
+        let json = json!({
+            "ab": 1.0,
+            "abc": 2.0, // too long
+            "c": 3.0, // too short
+            "cd": "x", // not a number
+            "de": -1.0, // negative
+            "De": 1.0, // case duplicate
+        });
+        let alphabet = vec!['a', 'b', 'c', 'd', 'e'];
+
+        let (digraphs, issues) = Digraphs::parse(json.as_object().unwrap(), &alphabet);
+
+        assert_eq!(
+            issues,
+            vec![
+                DigraphIssue::MalformedKey("abc".to_string()),
+                DigraphIssue::MalformedKey("c".to_string()),
+                DigraphIssue::InvalidValue("cd".to_string(), "\"x\"".to_string()),
+                DigraphIssue::NegativeValue("de".to_string(), -1.0),
+                DigraphIssue::CaseDuplicate("De".to_string(), "de".to_string()),
+                DigraphIssue::MissingLetter('c'),
+            ]
+        );
+        assert_eq!(digraphs.calculate_score(&['a', 'b']), 1.);
+        assert_eq!(digraphs.calculate_score(&['c', 'd']), 0.);
+    }
+
+    #[test]
+    pub fn parse_should_report_asymmetric_outliers() {
+        let json = json!({
+            "ab": 2000.0,
+            "ba": 1.0,
+            "cd": 10.0,
+            "dc": 1.0,
+            "ef": 0.5,
+            "fe": 0.002,
+        });
+
+        let (_, issues) = Digraphs::parse(json.as_object().unwrap(), &[]);
+
+        assert_eq!(
+            issues,
+            vec![DigraphIssue::AsymmetricOutlier("ab".to_string(), 2000., 1.)]
+        );
+        assert!(!issues[0].is_error());
+    }
+
+    #[test]
+    pub fn blend_should_normalize_sources() {
+        let prose = Digraphs::new(json!({ "ab": 1.0, "cd": 3.0 }).as_object().unwrap());
+        let code = Digraphs::new(json!({ "ab": 10.0, "ef": 10.0 }).as_object().unwrap());
+
+        let target = Digraphs::blend(vec![(prose, 3.), (code, 1.)]);
+
+        assert_eq!(target.get_value(&'a', &'b'), 25. * 0.75 + 50. * 0.25);
+        assert_eq!(target.get_value(&'c', &'d'), 75. * 0.75);
+        assert_eq!(target.get_value(&'e', &'f'), 50. * 0.25);
+        assert_eq!(
+            target.calculate_component_scores(&['a', 'b'], &['e', 'f']),
+            vec![(25., 0.), (50., 50.)]
+        );
+    }
+
     #[test]
     pub fn test_child_iterator() {
-        let a = vec![1, 2, 3];
+        let a = [1, 2, 3];
         let mut iter = a.iter();
         iter.next();
 
-        let mut child_iter = iter.clone().into_iter();
+        let mut child_iter = iter.clone();
 
         assert_eq!(*child_iter.next().unwrap(), 2);
         assert_eq!(*child_iter.next().unwrap(), 3);
@@ -0,0 +1,89 @@
+use std::str::FromStr;
+
+/// How the two directions of a pair of letters are combined into a score.\
+/// Letters of a group do not have an order yet, so it is a weighting of asymmetry:
+/// it tells how lopsided a pair is, not in which order its keys are typed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DigraphsDirection {
+    /// `ab + ba`, the direction is ignored.
+    Both,
+
+    /// `dominant * max(ab, ba) + reverse * min(ab, ba)`, the same for `ab` and `ba`.\
+    /// A pair typed mostly in one order can be placed later to form an inward roll,
+    /// so `dominant > reverse` rewards such pairs over ones typed in both orders.
+    Dominant { dominant: f64, reverse: f64 },
+}
+
+impl DigraphsDirection {
+    /// Symmetric in its arguments, swapping the directions gives the same value.
+    pub fn combine(&self, straight: f64, reverted: f64) -> f64 {
+        match *self {
+            DigraphsDirection::Both => straight + reverted,
+            DigraphsDirection::Dominant { dominant, reverse } => {
+                dominant * straight.max(reverted) + reverse * straight.min(reverted)
+            }
+        }
+    }
+}
+
+/// `both` or `dominant:reverse` weights, for example `1.5:0.5`.
+impl FromStr for DigraphsDirection {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value == "both" {
+            return Ok(DigraphsDirection::Both);
+        }
+
+        let parse = |x: &str| {
+            x.trim()
+                .parse::<f64>()
+                .map_err(|_| format!("'{}' is not a number in '{}'.", x, value))
+        };
+
+        match value.split_once(':') {
+            Some((dominant, reverse)) => Ok(DigraphsDirection::Dominant {
+                dominant: parse(dominant)?,
+                reverse: parse(reverse)?,
+            }),
+            None => Err(format!(
+                "'{}' is not a direction, expected 'both' or 'dominant:reverse' weights.",
+                value
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_direction() {
+        assert_eq!("both".parse(), Ok(DigraphsDirection::Both));
+        assert_eq!(
+            "1.5:0.5".parse(),
+            Ok(DigraphsDirection::Dominant {
+                dominant: 1.5,
+                reverse: 0.5
+            })
+        );
+        assert!("1.5".parse::<DigraphsDirection>().is_err());
+        assert!("a:b".parse::<DigraphsDirection>().is_err());
+    }
+
+    #[test]
+    fn should_weight_asymmetry_regardless_of_order() {
+        let direction = DigraphsDirection::Dominant {
+            dominant: 1.5,
+            reverse: 0.5,
+        };
+
+        assert_eq!(direction.combine(3., 1.), 5.);
+        assert_eq!(direction.combine(1., 3.), 5.);
+        assert_eq!(
+            direction.combine(2., 2.),
+            DigraphsDirection::Both.combine(2., 2.)
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use itertools::Itertools;
+use std::{path::PathBuf, str::FromStr};
+
+/// A digraphs file and its share in a blend.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DigraphsSource {
+    pub path: PathBuf,
+    pub weight: f64,
+}
+
+/// Comma separated list of `path[:weight]`, for example `prose.json:0.6,code.json:0.4`.\
+/// The weight is `1` by default, weights do not have to sum to `1`.\
+/// A single file is used as is, so it can not have a weight.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DigraphsSources(pub Vec<DigraphsSource>);
+
+impl FromStr for DigraphsSource {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        // the weight is optional and paths may contain colons, like `C:\digraphs.json`.
+        let (path, weight) = match value.rsplit_once(':') {
+            Some((path, weight)) => match weight.parse::<f64>() {
+                Ok(weight) => (path, weight),
+                Err(_) => (value, 1.),
+            },
+            None => (value, 1.),
+        };
+
+        if path.is_empty() {
+            return Err(format!("'{}' does not have a path.", value));
+        }
+
+        if weight.is_nan() || weight <= 0. {
+            return Err(format!("'{}' must have a positive weight.", value));
+        }
+
+        Ok(DigraphsSource {
+            path: PathBuf::from(path),
+            weight,
+        })
+    }
+}
+
+impl FromStr for DigraphsSources {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parts = value
+            .split(',')
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty())
+            .collect_vec();
+        let sources: Vec<_> = parts
+            .iter()
+            .map(|x| DigraphsSource::from_str(x))
+            .try_collect()?;
+
+        if sources.is_empty() {
+            return Err("At least one digraphs file is expected.".to_string());
+        }
+
+        if let ([part], [source]) = (parts.as_slice(), sources.as_slice()) {
+            if source.path.as_os_str() != *part {
+                return Err(format!(
+                    "'{}' has a weight, but a single digraphs file is not blended.",
+                    part
+                ));
+            }
+        }
+
+        Ok(DigraphsSources(sources))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_weights() {
+        let actual: DigraphsSources = "prose.json:0.6, code.json:0.4,chat.json".parse().unwrap();
+
+        assert_eq!(
+            actual.0,
+            vec![
+                DigraphsSource {
+                    path: PathBuf::from("prose.json"),
+                    weight: 0.6
+                },
+                DigraphsSource {
+                    path: PathBuf::from("code.json"),
+                    weight: 0.4
+                },
+                DigraphsSource {
+                    path: PathBuf::from("chat.json"),
+                    weight: 1.
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_keep_colons_in_paths() {
+        let actual: DigraphsSource = "C:\\data\\digraphs.json".parse().unwrap();
+
+        assert_eq!(actual.path, PathBuf::from("C:\\data\\digraphs.json"));
+        assert_eq!(actual.weight, 1.);
+    }
+
+    #[test]
+    fn should_reject_invalid_weights() {
+        assert!("prose.json:0".parse::<DigraphsSources>().is_err());
+        assert!("prose.json:-1".parse::<DigraphsSources>().is_err());
+        assert!("".parse::<DigraphsSources>().is_err());
+        assert!("prose.json:0.5".parse::<DigraphsSources>().is_err());
+        assert!("prose.json:0.5,".parse::<DigraphsSources>().is_err());
+        assert!("prose.json,".parse::<DigraphsSources>().is_ok());
+    }
+}
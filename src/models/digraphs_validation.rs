@@ -0,0 +1,81 @@
+use std::{fmt, path::Path};
+
+/// Pairs with a direction ratio above this value are reported as suspicious.
+pub const ASYMMETRY_RATIO: f64 = 100.;
+
+/// Values of digraphs files are rounded to 0.001, so ratios of pairs with a smaller value
+/// below 10 rounding steps are noise, like "uo" 0.003 of "ou" 0.753, such pairs are skipped.
+pub const ASYMMETRY_FLOOR: f64 = 0.01;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DigraphIssue {
+    /// A key that is not exactly two characters long.
+    MalformedKey(String),
+
+    /// A value that is not a number.
+    InvalidValue(String, String),
+
+    NegativeValue(String, f64),
+
+    /// Keys that are equal when lowercased, like "Th" and "th".
+    CaseDuplicate(String, String),
+
+    /// A letter of the alphabet that is not used by any digraph.
+    MissingLetter(char),
+
+    /// Both directions are present, but one is much heavier than the other.
+    AsymmetricOutlier(String, f64, f64),
+}
+
+impl DigraphIssue {
+    /// Warnings never fail a run, even in the strict mode.
+    pub fn is_error(&self) -> bool {
+        !matches!(self, DigraphIssue::AsymmetricOutlier(..))
+    }
+}
+
+impl fmt::Display for DigraphIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DigraphIssue::MalformedKey(key) => {
+                write!(f, "'{}' is not a digraph, expected exactly 2 letters", key)
+            }
+            DigraphIssue::InvalidValue(key, value) => {
+                write!(f, "'{}' has a non-numeric value {}", key, value)
+            }
+            DigraphIssue::NegativeValue(key, value) => {
+                write!(f, "'{}' has a negative value {}", key, value)
+            }
+            DigraphIssue::CaseDuplicate(first, second) => {
+                write!(f, "'{}' and '{}' differ only by case", first, second)
+            }
+            DigraphIssue::MissingLetter(letter) => {
+                write!(f, "'{}' is not used in any digraph", letter)
+            }
+            DigraphIssue::AsymmetricOutlier(key, straight, reverted) => write!(
+                f,
+                "'{}' is {:.3}, but the reverted pair is {:.3}",
+                key, straight, reverted
+            ),
+        }
+    }
+}
+
+/// Prints all issues and fails in the strict mode if there is at least one error.
+pub fn report_issues(path: &Path, issues: &[DigraphIssue], strict: bool) -> Result<(), String> {
+    for issue in issues {
+        let level = if strict && issue.is_error() {
+            "error"
+        } else {
+            "warning"
+        };
+        eprintln!("{}: {}: {}", level, path.display(), issue);
+    }
+
+    let errors_count = issues.iter().filter(|x| x.is_error()).count();
+    if strict && errors_count > 0 {
+        return Err(format!("{} has {} errors.", path.display(), errors_count));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use itertools::Itertools;
+use std::str::FromStr;
+
+/// Comma separated maximum numbers of letters in each group,
+/// for example `3,3,4,4,4,4,3,3` for eight fingers from the left pinky to the right one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupCapacities(pub Vec<usize>);
+
+impl GroupCapacities {
+    pub fn total(&self) -> usize {
+        self.0.iter().sum()
+    }
+}
+
+impl FromStr for GroupCapacities {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let capacities: Vec<usize> = value
+            .split(',')
+            .map(|x| x.trim())
+            .map(|x| {
+                x.parse::<usize>()
+                    .ok()
+                    .filter(|&x| x > 0)
+                    .ok_or_else(|| format!("'{}' is not a positive number in '{}'.", x, value))
+            })
+            .try_collect()?;
+
+        if capacities.len() < 2 {
+            return Err(format!("'{}' must have at least 2 groups.", value));
+        }
+
+        Ok(GroupCapacities(capacities))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse() {
+        let actual: GroupCapacities = "3, 3,4,4".parse().unwrap();
+
+        assert_eq!(actual.0, vec![3, 3, 4, 4]);
+        assert_eq!(actual.total(), 14);
+    }
+
+    #[test]
+    fn should_reject_invalid_capacities() {
+        assert!("4".parse::<GroupCapacities>().is_err());
+        assert!("4,0".parse::<GroupCapacities>().is_err());
+        assert!("4,,4".parse::<GroupCapacities>().is_err());
+        assert!("4,a".parse::<GroupCapacities>().is_err());
+    }
+}
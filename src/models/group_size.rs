@@ -0,0 +1,83 @@
+use std::{fmt, str::FromStr};
+
+/// Allowed number of letters on a hand.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GroupSize {
+    pub min: usize,
+    pub max: usize,
+}
+
+impl GroupSize {
+    pub fn exact(size: usize) -> GroupSize {
+        GroupSize {
+            min: size,
+            max: size,
+        }
+    }
+
+    pub fn contains(&self, size: usize) -> bool {
+        self.min <= size && size <= self.max
+    }
+}
+
+/// `13-15` or just `15` for an exact size.
+impl FromStr for GroupSize {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parse = |x: &str| {
+            x.trim()
+                .parse::<usize>()
+                .map_err(|_| format!("'{}' is not a number in '{}'.", x, value))
+        };
+
+        let size = match value.split_once('-') {
+            Some((min, max)) => GroupSize {
+                min: parse(min)?,
+                max: parse(max)?,
+            },
+            None => GroupSize::exact(parse(value)?),
+        };
+
+        if size.min > size.max {
+            return Err(format!("'{}' has min bigger than max.", value));
+        }
+
+        Ok(size)
+    }
+}
+
+impl fmt::Display for GroupSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.min == self.max {
+            write!(f, "{}", self.min)
+        } else {
+            write!(f, "{}-{}", self.min, self.max)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse() {
+        assert_eq!("13-15".parse(), Ok(GroupSize { min: 13, max: 15 }));
+        assert_eq!("15".parse(), Ok(GroupSize::exact(15)));
+        assert_eq!(GroupSize { min: 13, max: 15 }.to_string(), "13-15");
+
+        assert!("15-13".parse::<GroupSize>().is_err());
+        assert!("a-13".parse::<GroupSize>().is_err());
+    }
+
+    #[test]
+    fn should_contain_bounds() {
+        let size = GroupSize { min: 13, max: 15 };
+
+        assert!(size.contains(13));
+        assert!(size.contains(15));
+        assert!(!size.contains(12));
+        assert!(!size.contains(16));
+    }
+}
@@ -1,5 +1,25 @@
+mod balance;
 mod digraphs;
+mod digraphs_direction;
+mod digraphs_sources;
+mod digraphs_validation;
+mod group_capacities;
+mod group_size;
+mod normalization;
+mod output;
+mod records;
 mod shared;
+mod split;
 
+pub use balance::*;
 pub use digraphs::*;
+pub use digraphs_direction::*;
+pub use digraphs_sources::*;
+pub use digraphs_validation::*;
+pub use group_capacities::*;
+pub use group_size::*;
+pub use normalization::*;
+pub use output::*;
+pub use records::*;
 pub use shared::*;
+pub use split::*;
@@ -0,0 +1,297 @@
+use itertools::Itertools;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    str::FromStr,
+};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// What happens with punctuation and symbols of a text.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PunctuationPolicy {
+    /// Punctuation separates words, `don't` becomes `don` and `t`.
+    Split,
+
+    /// Punctuation is removed, `don't` becomes `dont`.
+    Strip,
+
+    /// Punctuation stays in words, like other characters.
+    Keep,
+}
+
+impl FromStr for PunctuationPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "split" => Ok(PunctuationPolicy::Split),
+            "strip" => Ok(PunctuationPolicy::Strip),
+            "keep" => Ok(PunctuationPolicy::Keep),
+            _ => Err(format!(
+                "'{}' is not a punctuation policy, expected split, strip or keep.",
+                value
+            )),
+        }
+    }
+}
+
+/// What happens with characters missed on a keyboard.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnknownCharsPolicy {
+    /// Words with such characters are dropped.
+    Skip,
+
+    /// The text is rejected.
+    Fail,
+
+    /// Characters are replaced with keyboard ones,
+    /// words with not mapped characters are dropped.
+    Map(HashMap<char, char>),
+}
+
+/// `skip`, `fail` or `map:from=to,...` like `map:ä=a,ß=s`.
+impl FromStr for UnknownCharsPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "skip" => return Ok(UnknownCharsPolicy::Skip),
+            "fail" => return Ok(UnknownCharsPolicy::Fail),
+            _ => {}
+        }
+
+        let pairs = value.strip_prefix("map:").ok_or_else(|| {
+            format!(
+                "'{}' is not an unknown characters policy, expected skip, fail or map:from=to,...",
+                value
+            )
+        })?;
+
+        pairs
+            .split(',')
+            .map(|pair| match pair.chars().collect_vec()[..] {
+                [from, '=', to] => Ok((from, to)),
+                _ => Err(format!(
+                    "'{}' is not a 'from=to' pair in '{}'.",
+                    pair, value
+                )),
+            })
+            .try_collect()
+            .map(UnknownCharsPolicy::Map)
+    }
+}
+
+/// Steps applied to a sample text before it is scored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextNormalization {
+    pub lowercase: bool,
+
+    /// Removes accents, like `é` to `e`.
+    pub fold: bool,
+    pub punctuation: PunctuationPolicy,
+    pub unknown_chars: UnknownCharsPolicy,
+}
+
+/// What the normalization changed in a text.
+#[derive(Debug, Default, PartialEq)]
+pub struct NormalizationSummary {
+    /// All words of the text with their counts.
+    pub words_count: u64,
+    pub dropped_count: u64,
+    pub mapped_count: u64,
+
+    /// Characters which made words dropped.
+    pub unknown_chars: BTreeMap<char, u64>,
+}
+
+impl NormalizationSummary {
+    pub fn is_empty(&self) -> bool {
+        self.dropped_count == 0 && self.mapped_count == 0
+    }
+}
+
+impl fmt::Display for NormalizationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Dropped {} of {} words, mapped {} characters.",
+            self.dropped_count, self.words_count, self.mapped_count
+        )?;
+
+        if !self.unknown_chars.is_empty() {
+            let chars = self
+                .unknown_chars
+                .iter()
+                .sorted_by(|(a_char, a_count), (b_char, b_count)| {
+                    b_count.cmp(a_count).then_with(|| a_char.cmp(b_char))
+                })
+                .map(|(letter, count)| format!("{:?}: {}", letter, count))
+                .join(", ");
+            write!(f, " Unknown characters: {}.", chars)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TextNormalization {
+    /// Splits parts of a text with their counts into words of the alphabet.\
+    /// A part can be a whole text or a word of a frequency list.
+    pub fn normalize<'a>(
+        &self,
+        parts: impl IntoIterator<Item = (&'a str, u64)>,
+        alphabet: &HashSet<char>,
+    ) -> Result<(Vec<(String, u64)>, NormalizationSummary), String> {
+        let mut words = Vec::new();
+        let mut summary = NormalizationSummary::default();
+
+        for (part, count) in parts {
+            for word in self.split(part) {
+                summary.words_count += count;
+
+                match self.to_alphabet(&word, alphabet) {
+                    Ok((word, mapped)) => {
+                        summary.mapped_count += mapped * count;
+                        words.push((word, count));
+                    }
+                    Err(unknown) => {
+                        if self.unknown_chars == UnknownCharsPolicy::Fail {
+                            return Err(format!(
+                                "'{}' of '{}' is not on the keyboard.",
+                                unknown, word
+                            ));
+                        }
+
+                        summary.dropped_count += count;
+                        *summary.unknown_chars.entry(unknown).or_default() += count;
+                    }
+                }
+            }
+        }
+
+        Ok((words, summary))
+    }
+
+    fn split(&self, text: &str) -> Vec<String> {
+        let text = if self.lowercase {
+            text.to_lowercase()
+        } else {
+            text.to_string()
+        };
+        let text: String = if self.fold {
+            text.nfd().filter(|&x| !is_combining_mark(x)).collect()
+        } else {
+            text
+        };
+        let is_punctuation = |x: char| !x.is_alphanumeric() && !x.is_whitespace();
+        let text: String = match self.punctuation {
+            PunctuationPolicy::Split => text
+                .chars()
+                .map(|x| if is_punctuation(x) { ' ' } else { x })
+                .collect(),
+            PunctuationPolicy::Strip => text.chars().filter(|&x| !is_punctuation(x)).collect(),
+            PunctuationPolicy::Keep => text,
+        };
+
+        text.split_whitespace().map_into().collect()
+    }
+
+    /// The word with mapped characters and their number, or the first unknown character.
+    fn to_alphabet(&self, word: &str, alphabet: &HashSet<char>) -> Result<(String, u64), char> {
+        let mut mapped = 0;
+        let word = word
+            .chars()
+            .map(|x| match &self.unknown_chars {
+                _ if alphabet.contains(&x) => Ok(x),
+                UnknownCharsPolicy::Map(map) => match map.get(&x) {
+                    Some(&to) if alphabet.contains(&to) => {
+                        mapped += 1;
+                        Ok(to)
+                    }
+                    _ => Err(x),
+                },
+                _ => Err(x),
+            })
+            .collect::<Result<String, char>>()?;
+
+        Ok((word, mapped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_normalization() -> TextNormalization {
+        TextNormalization {
+            lowercase: true,
+            fold: true,
+            punctuation: PunctuationPolicy::Split,
+            unknown_chars: UnknownCharsPolicy::Skip,
+        }
+    }
+
+    fn alphabet() -> HashSet<char> {
+        ('a'..='z').collect()
+    }
+
+    #[test]
+    fn should_normalize_text() {
+        let text = "Café  don't\nstop 42 times,\tnaïve";
+
+        let (words, summary) = default_normalization()
+            .normalize([(text, 1)], &alphabet())
+            .unwrap();
+
+        assert_eq!(
+            words.iter().map(|(x, _)| x.as_str()).collect_vec(),
+            vec!["cafe", "don", "t", "stop", "times", "naive"]
+        );
+        assert_eq!(summary.words_count, 7);
+        assert_eq!(summary.dropped_count, 1);
+        assert_eq!(summary.unknown_chars, [('4', 1)].iter().cloned().collect());
+    }
+
+    #[test]
+    fn should_apply_policies() {
+        let mut normalization = default_normalization();
+        normalization.punctuation = PunctuationPolicy::Strip;
+        normalization.unknown_chars = "map:ß=s,ü=u".parse().unwrap();
+        normalization.fold = false;
+
+        let (words, summary) = normalization
+            .normalize(
+                [("don't", 2), ("Straße", 3), ("über", 1), ("é", 1)],
+                &alphabet(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            words,
+            vec![
+                ("dont".to_string(), 2),
+                ("strase".to_string(), 3),
+                ("uber".to_string(), 1)
+            ]
+        );
+        assert_eq!(summary.mapped_count, 4);
+        assert_eq!(summary.dropped_count, 1);
+
+        normalization.unknown_chars = UnknownCharsPolicy::Fail;
+        assert!(normalization.normalize([("é", 1)], &alphabet()).is_err());
+    }
+
+    #[test]
+    fn should_parse_policies() {
+        assert_eq!("keep".parse(), Ok(PunctuationPolicy::Keep));
+        assert_eq!("skip".parse(), Ok(UnknownCharsPolicy::Skip));
+        assert_eq!(
+            "map:ä=a".parse(),
+            Ok(UnknownCharsPolicy::Map(
+                [('ä', 'a')].iter().cloned().collect()
+            ))
+        );
+        assert!("map:äa".parse::<UnknownCharsPolicy>().is_err());
+        assert!("drop".parse::<UnknownCharsPolicy>().is_err());
+    }
+}
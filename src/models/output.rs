@@ -0,0 +1,237 @@
+use super::DynError;
+use itertools::Itertools;
+use serde::{
+    de::{self, DeserializeOwned},
+    Deserialize, Deserializer, Serialize,
+};
+use serde_json::Value;
+use std::str::FromStr;
+
+/// A result that can be rendered in any of `OutputFormat`.
+pub trait ResultRecord: Serialize + DeserializeOwned {
+    /// The original semicolon separated line.
+    fn to_legacy(&self) -> String;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// Semicolon separated lines without headers.
+    Legacy,
+    Json,
+    JsonLines,
+    Csv,
+}
+
+impl OutputFormat {
+    /// Legacy files keep their `csv` extension, CSV files with headers are `table.csv`.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Legacy => "csv",
+            OutputFormat::Csv => "table.csv",
+            OutputFormat::Json => "json",
+            OutputFormat::JsonLines => "jsonl",
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "legacy" => Ok(OutputFormat::Legacy),
+            "json" => Ok(OutputFormat::Json),
+            "jsonl" => Ok(OutputFormat::JsonLines),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(format!(
+                "'{}' is not a format, expected legacy, json, jsonl or csv.",
+                value
+            )),
+        }
+    }
+}
+
+pub fn format_records<T: ResultRecord>(
+    records: &[T],
+    format: OutputFormat,
+) -> Result<String, DynError> {
+    let text = match format {
+        OutputFormat::Legacy => records.iter().map(|x| x.to_legacy()).join("\n"),
+        OutputFormat::Json => serde_json::to_string_pretty(records)?,
+        OutputFormat::JsonLines => records
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()?
+            .join("\n"),
+        OutputFormat::Csv => format_csv(records)?,
+    };
+
+    Ok(text)
+}
+
+/// Detects the format by the content, so files in any format can be loaded.\
+/// Legacy lines are passed to `parse_legacy`.
+pub fn parse_records<T: ResultRecord>(
+    text: &str,
+    parse_legacy: impl Fn(&str) -> Option<T>,
+) -> Result<Vec<T>, DynError> {
+    let text = text.trim();
+    let first_line = text.lines().next().unwrap_or_default();
+
+    if text.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    if text.starts_with('[') {
+        return Ok(serde_json::from_str(text)?);
+    }
+
+    if text.starts_with('{') {
+        return Ok(text
+            .lines()
+            .filter(|x| !x.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<_>, _>>()?);
+    }
+
+    if first_line.contains(';') {
+        return text
+            .lines()
+            .filter(|x| !x.trim().is_empty())
+            .map(|x| parse_legacy(x).ok_or_else(|| format!("'{}' is not a valid line.", x).into()))
+            .collect();
+    }
+
+    let records = csv::Reader::from_reader(text.as_bytes())
+        .deserialize()
+        .collect::<Result<Vec<T>, _>>()?;
+
+    Ok(records)
+}
+
+/// Lists and nested values of CSV records are JSON texts in one column,
+/// fields with them are read by `nested` from both CSV and JSON.
+pub fn nested<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Default,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Nested<T> {
+        Text(String),
+        Value(T),
+    }
+
+    match Nested::<T>::deserialize(deserializer)? {
+        Nested::Text(text) if text.is_empty() => Ok(T::default()),
+        Nested::Text(text) => serde_json::from_str(&text).map_err(de::Error::custom),
+        Nested::Value(value) => Ok(value),
+    }
+}
+
+/// Each field is a column, lists and nested values are JSON texts like `[1,2]`.
+fn format_csv<T: Serialize>(records: &[T]) -> Result<String, DynError> {
+    let rows = records
+        .iter()
+        .map(|x| match serde_json::to_value(x)? {
+            Value::Object(map) => Ok(map
+                .into_iter()
+                .map(|(name, value)| (name, to_cell(value)))
+                .collect_vec()),
+            _ => Err("Only records can be written to CSV.".into()),
+        })
+        .collect::<Result<Vec<_>, DynError>>()?;
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    if let Some(first) = rows.first() {
+        writer.write_record(first.iter().map(|(name, _)| name))?;
+    }
+    for row in rows.iter() {
+        writer.write_record(row.iter().map(|(_, value)| value))?;
+    }
+
+    Ok(String::from_utf8(writer.into_inner()?)?
+        .trim_end()
+        .to_string())
+}
+
+fn to_cell(value: Value) -> String {
+    match value {
+        Value::String(text) => text,
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Record {
+        name: String,
+        score: f64,
+    }
+
+    impl ResultRecord for Record {
+        fn to_legacy(&self) -> String {
+            format!("{}; {};", self.name, self.score)
+        }
+    }
+
+    fn parse_legacy(line: &str) -> Option<Record> {
+        let parts = line.split(';').map(|x| x.trim()).collect_vec();
+        Some(Record {
+            name: parts.first()?.to_string(),
+            score: parts.get(1)?.parse().ok()?,
+        })
+    }
+
+    fn default_records() -> Vec<Record> {
+        vec![
+            Record {
+                name: "a, b".to_string(),
+                score: 1.5,
+            },
+            Record {
+                name: "c".to_string(),
+                score: 2.,
+            },
+        ]
+    }
+
+    #[test]
+    fn should_write_csv_with_headers() {
+        let actual = format_records(&default_records(), OutputFormat::Csv).unwrap();
+
+        assert_eq!(actual, "name,score\n\"a, b\",1.5\nc,2.0");
+    }
+
+    #[test]
+    fn should_write_nested_values_as_json() {
+        let value = serde_json::json!({ "a": 1, "b": [{ "c": 2 }, { "c": 3 }] });
+
+        let actual = format_csv(&[value]).unwrap();
+
+        assert_eq!(actual, "a,b\n1,\"[{\"\"c\"\":2},{\"\"c\"\":3}]\"");
+    }
+
+    #[test]
+    fn should_read_all_formats() {
+        for format in [
+            OutputFormat::Json,
+            OutputFormat::JsonLines,
+            OutputFormat::Csv,
+        ] {
+            let text = format_records(&default_records(), format).unwrap();
+            let actual: Vec<Record> = parse_records(&text, parse_legacy).unwrap();
+
+            assert_eq!(actual, default_records(), "{:?}", format);
+        }
+
+        let actual = parse_records("c; 2;\nd; 3;", parse_legacy).unwrap();
+        assert_eq!(actual[1].name, "d");
+    }
+}
@@ -0,0 +1,241 @@
+use super::{calculate_score, format_result, get_letters_id, nested, BalancePenalty, ResultRecord};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+/// Scores of a blended digraphs source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComponentScores {
+    pub left: f64,
+    pub right: f64,
+}
+
+/// A split of letters with all its metrics.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LettersRecord {
+    /// A hash of the letters, the same split has the same id in all runs.
+    #[serde(default)]
+    pub id: String,
+    pub left: String,
+    pub left_count: usize,
+    pub left_score: f64,
+    pub right: String,
+    pub right_count: usize,
+    pub right_score: f64,
+
+    /// The balance factor, `BalancePenalty::balance_factor`.
+    pub factor: f64,
+
+    /// Sum of left and right scores.
+    pub total: f64,
+
+    /// The total with the applied balance factor.
+    pub score: f64,
+
+    #[serde(default, deserialize_with = "nested")]
+    pub components: Vec<ComponentScores>,
+}
+
+impl LettersRecord {
+    pub fn new(
+        left: &[char],
+        right: &[char],
+        left_score: f64,
+        right_score: f64,
+        penalty: &BalancePenalty,
+        components: &[(f64, f64)],
+    ) -> LettersRecord {
+        LettersRecord {
+            id: get_letters_id(left, right),
+            left: left.iter().collect(),
+            left_count: left.len(),
+            left_score,
+            right: right.iter().collect(),
+            right_count: right.len(),
+            right_score,
+            factor: penalty.balance_factor(left_score, right_score),
+            total: left_score + right_score,
+            score: calculate_score(left_score, right_score, penalty),
+            components: components
+                .iter()
+                .map(|&(left, right)| ComponentScores { left, right })
+                .collect(),
+        }
+    }
+
+    /// Parses a line of `format_result`.\
+    /// Old lines have no id, it is calculated from the letters.
+    pub fn from_legacy(line: &str) -> Option<LettersRecord> {
+        let parts = line
+            .split(';')
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty())
+            .collect_vec();
+        let number = |i: usize| parts.get(i)?.parse::<f64>().ok();
+
+        // components come in pairs, so an odd tail starts with the id.
+        let components_start = if parts.len() > 9 && (parts.len() - 9) % 2 == 1 {
+            10
+        } else {
+            9
+        };
+        let components = parts
+            .iter()
+            .skip(components_start)
+            .map(|x| x.parse::<f64>().ok())
+            .collect::<Option<Vec<_>>>()?
+            .chunks(2)
+            .map(|x| match *x {
+                [left, right] => Some(ComponentScores { left, right }),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let left = parts.get(1)?.to_string();
+        let right = parts.get(4)?.to_string();
+
+        Some(LettersRecord {
+            id: get_letters_id(&left.chars().collect_vec(), &right.chars().collect_vec()),
+            left_count: parts.first()?.parse().ok()?,
+            left,
+            left_score: number(2)?,
+            right_count: parts.get(3)?.parse().ok()?,
+            right,
+            right_score: number(5)?,
+            factor: number(6)?,
+            total: number(7)?,
+            score: number(8)?,
+            components,
+        })
+    }
+}
+
+impl ResultRecord for LettersRecord {
+    fn to_legacy(&self) -> String {
+        format_result(
+            &self.left.chars().collect_vec(),
+            &self.right.chars().collect_vec(),
+            self.left_score,
+            self.right_score,
+            self.factor,
+            self.score,
+            &self
+                .components
+                .iter()
+                .map(|x| (x.left, x.right))
+                .collect_vec(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{format_records, parse_records, Digraphs, OutputFormat};
+
+    #[test]
+    fn should_parse_legacy_line() {
+        // a row of data/letters.md with its id and components appended.
+        let line = "15; abcejkmpqrsuvxz; 27.723; 11; dfghilnotwy; 26.999; 0.997; 54.722; 54.283; 44d75a5029; 1.000; 2.000;";
+
+        let actual = LettersRecord::from_legacy(line).unwrap();
+
+        assert_eq!(actual.id, "44d75a5029");
+        assert_eq!(actual.left, "abcejkmpqrsuvxz");
+        assert_eq!(actual.right_count, 11);
+        assert_eq!(actual.factor, 0.997);
+        assert_eq!(actual.score, 54.283);
+        assert_eq!(
+            actual.components,
+            vec![ComponentScores {
+                left: 1.,
+                right: 2.
+            }]
+        );
+        assert_eq!(actual.to_legacy(), line);
+    }
+
+    #[test]
+    fn should_parse_legacy_line_without_id() {
+        let line = "15; abcejkmpqrsuvxz; 27.723; 11; dfghilnotwy; 26.999; 0.997; 54.722; 54.283;";
+
+        let actual = LettersRecord::from_legacy(line).unwrap();
+
+        assert_eq!(actual.id, "44d75a5029");
+        assert_eq!(actual.score, 54.283);
+        assert!(actual.components.is_empty());
+    }
+
+    #[test]
+    fn should_render_rows_of_letters_md() {
+        let alphabet = ('a'..='z').collect_vec();
+        let digraphs = Digraphs::load(&"data/digraphs.json".into(), &alphabet, true).unwrap();
+        let text = std::fs::read_to_string("data/letters.md").unwrap();
+        let rows = text.lines().filter(|x| x.ends_with(';')).collect_vec();
+
+        assert!(!rows.is_empty());
+        for row in rows {
+            let parsed = LettersRecord::from_legacy(row).unwrap();
+            let left = parsed.left.chars().collect_vec();
+            let right = parsed.right.chars().collect_vec();
+            let record = LettersRecord::new(
+                &left,
+                &right,
+                digraphs.calculate_score(&left),
+                digraphs.calculate_score(&right),
+                &BalancePenalty::Letters,
+                &[],
+            );
+
+            assert_eq!(record.to_legacy(), format!("{} {};", row, record.id));
+        }
+    }
+
+    #[test]
+    fn should_ignore_order_of_letters_in_id() {
+        assert_eq!(
+            get_letters_id(&['b', 'a'], &['d', 'c']),
+            get_letters_id(&['a', 'b'], &['c', 'd'])
+        );
+        assert_ne!(
+            get_letters_id(&['a', 'b'], &['c', 'd']),
+            get_letters_id(&['c', 'd'], &['a', 'b'])
+        );
+    }
+
+    #[test]
+    fn should_read_components_from_all_formats() {
+        let record = LettersRecord::new(
+            &['a'],
+            &['b', 'c'],
+            1.,
+            2.,
+            &BalancePenalty::Letters,
+            &[(3., 4.)],
+        );
+        let records = vec![
+            record.clone(),
+            LettersRecord {
+                components: Vec::new(),
+                ..record
+            },
+        ];
+
+        let csv = format_records(&records, OutputFormat::Csv).unwrap();
+        assert_eq!(
+            csv.lines().next().unwrap(),
+            "id,left,left_count,left_score,right,right_count,right_score,factor,total,score,components"
+        );
+
+        for format in [
+            OutputFormat::Json,
+            OutputFormat::JsonLines,
+            OutputFormat::Csv,
+        ] {
+            let text = format_records(&records, format).unwrap();
+
+            let actual = parse_records(&text, LettersRecord::from_legacy).unwrap();
+
+            assert_eq!(actual, records, "{:?}", format);
+        }
+    }
+}
@@ -1,5 +1,10 @@
+use super::{
+    BalancePenalty, DigraphsDirection, DigraphsSources, GroupCapacities, GroupSize, OutputFormat,
+    PunctuationPolicy, Split, UnknownCharsPolicy,
+};
+use itertools::Itertools;
 use rand::{distributions::Alphanumeric, Rng};
-use std::{cmp::Ordering, error::Error, path::PathBuf};
+use std::{error::Error, path::PathBuf};
 use structopt::StructOpt;
 
 #[derive(StructOpt)]
@@ -8,12 +13,65 @@ pub struct CliSettings {
     #[structopt(short = "k", long = "keyboard")]
     pub keyboard: Option<PathBuf>,
 
-    /// sample text
+    /// check the keyboard settings and exit, the keyboard optimizer checks them before runs
+    #[structopt(long = "validate")]
+    pub validate: bool,
+
+    /// sample text or a frequency list with `word<TAB>count` lines
     #[structopt(short = "t", long = "text")]
     pub text: Option<PathBuf>,
 
+    /// keep upper case letters of the text
+    #[structopt(long = "keep-case")]
+    pub keep_case: bool,
+
+    /// keep accents of the text, by default they are removed like `é` to `e`
+    #[structopt(long = "keep-accents")]
+    pub keep_accents: bool,
+
+    /// punctuation of the text: split words by it, strip it or keep it
+    #[structopt(long = "punctuation", default_value = "split")]
+    pub punctuation: PunctuationPolicy,
+
+    /// characters of the text missed on the keyboard: skip words with them, fail
+    /// or map them like `map:ä=a,ß=s`
+    #[structopt(long = "unknown-chars", default_value = "skip")]
+    pub unknown_chars: UnknownCharsPolicy,
+
+    /// digraphs files with optional weights: `prose.json:0.6,code.json:0.4`
     #[structopt(short = "d", long = "digraphs")]
-    pub digraphs: Option<PathBuf>,
+    pub digraphs: Option<DigraphsSources>,
+
+    /// how the two orders of a pair are weighted: `both` sums them, `dominant:reverse` weights
+    /// the more and the less frequent one, like `1.5:0.5`, so lopsided pairs score higher.\
+    /// it does not depend on the order keys are typed in
+    #[structopt(long = "digraphs-direction", default_value = "both")]
+    pub digraphs_direction: DigraphsDirection,
+
+    /// fail on invalid digraphs instead of printing warnings
+    #[structopt(long = "strict")]
+    pub strict: bool,
+
+    /// a left/right split: a line of the letters output or 2 groups of letters.\
+    /// the keyboard optimizer keeps letters of the split on their hands
+    #[structopt(long = "split")]
+    pub split: Option<Split>,
+
+    /// balance penalty curve: letters, keyboard, linear:slope, quadratic:slope,
+    /// exponential:slope, hinge:tolerance:slope or target:ratio:slope.\
+    /// letters optimizers use `letters` by default and the keyboard one uses `keyboard`
+    #[structopt(long = "balance-penalty")]
+    pub balance_penalty: Option<BalancePenalty>,
+
+    /// a results file to seed the letters optimizer, like `data/letters.md`
+    #[structopt(long = "seed")]
+    pub seed: Option<PathBuf>,
+
+    /// results format: legacy, json, jsonl or csv.\
+    /// genetic optimizers start from and overwrite `data/keyboards.*` or `data/letters.*`
+    /// with the extension of the format, like the tracked `data/keyboards.csv`
+    #[structopt(long = "output-format", default_value = "legacy")]
+    pub output_format: OutputFormat,
 
     #[structopt(long = "frozen-left", default_value = "")]
     pub frozen_left: String,
@@ -41,6 +99,26 @@ pub struct CliSettings {
     #[structopt(short = "l", long = "left-count", default_value = "15")]
     pub left_count: u8,
 
+    /// allowed left group sizes of the letters optimizer, like `13-15`, `left-count` by default
+    #[structopt(long = "left-size")]
+    pub left_size: Option<GroupSize>,
+
+    /// allowed right group sizes of the letters optimizer, like `11-13`
+    #[structopt(long = "right-size")]
+    pub right_size: Option<GroupSize>,
+
+    /// capacities of letter groups of the groups optimizer, like `3,3,4,4,4,4,3,3` for fingers
+    #[structopt(long = "groups", default_value = "3,3,4,4,4,4,3,3")]
+    pub groups: GroupCapacities,
+
+    /// the smallest left group of the greedy solver, it reports groups from `left-count` down to it
+    #[structopt(long = "min-left-count", default_value = "11")]
+    pub min_left_count: u8,
+
+    /// how many best splits the greedy solver keeps on each step
+    #[structopt(long = "beam-width", default_value = "1")]
+    pub beam_width: u8,
+
     /// how much we continue on the same result.\
     /// if generations are not evolving not much sense to continue.
     #[structopt(long = "repeats-count", default_value = "100")]
@@ -49,6 +127,8 @@ pub struct CliSettings {
 
 pub type DynError = Box<dyn Error>;
 
+/// A random id of a birth, children keep ids of parents to know their lineage.\
+/// The same letters or layout get a new version each time they are born, see `get_id`.
 pub fn get_version() -> String {
     rand::thread_rng()
         .sample_iter(&Alphanumeric)
@@ -57,54 +137,80 @@ pub fn get_version() -> String {
         .collect()
 }
 
+/// A short hash of the canonical content, like `git` ones.\
+/// The same letters or layout get the same id in all runs and files.
+pub fn get_id(canonical: &str) -> String {
+    let mut hash = sha1_smol::Sha1::from(canonical).digest().to_string();
+    hash.truncate(10);
+    hash
+}
+
+/// Groups are sorted, so the order of letters does not matter.
+pub fn get_letters_id(left_letters: &[char], right_letters: &[char]) -> String {
+    let left: String = left_letters.iter().sorted().collect();
+    let right: String = right_letters.iter().sorted().collect();
+
+    get_id(&format!("{} {}", left, right))
+}
+
 pub fn print_letters(
-    left_letters: &Vec<char>,
-    right_letters: &Vec<char>,
+    left_letters: &[char],
+    right_letters: &[char],
     left_score: f64,
     right_score: f64,
+    penalty: &BalancePenalty,
+    components: &[(f64, f64)],
 ) {
     println!(
         "{}",
-        format_result(left_letters, right_letters, left_score, right_score)
+        format_result(
+            left_letters,
+            right_letters,
+            left_score,
+            right_score,
+            penalty.balance_factor(left_score, right_score),
+            calculate_score(left_score, right_score, penalty),
+            components
+        )
     );
 }
 
+/// `factor` is the balance factor of the scores and `score` is the total with the balance.\
+/// `components` are left and right scores for each blended digraphs source,
+/// they are appended after the total score and the id.
 pub fn format_result(
-    left_letters: &Vec<char>,
-    right_letters: &Vec<char>,
+    left_letters: &[char],
+    right_letters: &[char],
     left_score: f64,
     right_score: f64,
+    factor: f64,
+    score: f64,
+    components: &[(f64, f64)],
 ) -> String {
     let left_string: String = left_letters.iter().collect();
     let right_string: String = right_letters.iter().collect();
 
+    let components: String = components
+        .iter()
+        .map(|(left, right)| format!(" {:.3}; {:.3};", left, right))
+        .collect();
+
     format!(
-        "{}; {}; {:.3}; {}; {}; {:.3}; {:.3}; {:.3}; {:.3};",
+        "{}; {}; {:.3}; {}; {}; {:.3}; {:.3}; {:.3}; {:.3}; {};{}",
         left_letters.len(),
         left_string,
         left_score,
         right_letters.len(),
         right_string,
         right_score,
-        get_factor(left_score, right_score),
+        factor,
         left_score + right_score,
-        calculate_score(left_score, right_score)
+        score,
+        get_letters_id(left_letters, right_letters),
+        components
     )
 }
 
-fn get_factor(left_score: f64, right_score: f64) -> f64 {
-    let factor = if left_score.partial_cmp(&right_score).unwrap() == Ordering::Less {
-        left_score / right_score
-    } else {
-        right_score / left_score
-    };
-
-    1.1 - 0.1 / factor
-}
-
-pub fn calculate_score(left: f64, right: f64) -> f64 {
-    let factor = get_factor(left, right);
-    let total = left + right;
-
-    total * factor * factor * factor
+pub fn calculate_score(left: f64, right: f64, penalty: &BalancePenalty) -> f64 {
+    (left + right) * penalty.score_factor(left, right)
 }
@@ -0,0 +1,75 @@
+use itertools::Itertools;
+use std::str::FromStr;
+
+/// Letters of the left and right hands.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Split {
+    pub left: Vec<char>,
+    pub right: Vec<char>,
+}
+
+/// Accepts a line of the letters output, like `15; abc...; 27.723; 11; def...; ...`,
+/// or just two groups of letters separated by a space.
+impl FromStr for Split {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let groups = if value.contains(';') {
+            let parts = value.split(';').map(|x| x.trim()).collect_vec();
+            match (parts.get(1), parts.get(4)) {
+                (Some(&left), Some(&right)) => (left, right),
+                _ => return Err(format!("'{}' is not a letters result line.", value)),
+            }
+        } else {
+            match value.split_whitespace().collect_tuple() {
+                Some(groups) => groups,
+                None => return Err(format!("'{}' must have 2 groups of letters.", value)),
+            }
+        };
+
+        let left = groups.0.chars().collect_vec();
+        let right = groups.1.chars().collect_vec();
+
+        if let Some(letter) = left.iter().find(|x| right.contains(x)) {
+            return Err(format!("'{}' is on both hands in '{}'.", letter, value));
+        }
+
+        if left.iter().chain(right.iter()).any(|x| !x.is_alphabetic()) {
+            return Err(format!("'{}' must contain only letters.", value));
+        }
+
+        Ok(Split { left, right })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_result_line() {
+        let actual: Split =
+            "15; abcejkmpqrsuvxz; 27.723; 11; dfghilnotwy; 26.999; 0.997; 54.722; 54.283;"
+                .parse()
+                .unwrap();
+
+        assert_eq!(actual.left, "abcejkmpqrsuvxz".chars().collect_vec());
+        assert_eq!(actual.right, "dfghilnotwy".chars().collect_vec());
+    }
+
+    #[test]
+    fn should_parse_groups() {
+        let actual: Split = "abc def".parse().unwrap();
+
+        assert_eq!(actual.left, vec!['a', 'b', 'c']);
+        assert_eq!(actual.right, vec!['d', 'e', 'f']);
+    }
+
+    #[test]
+    fn should_reject_invalid_splits() {
+        assert!("abc".parse::<Split>().is_err());
+        assert!("abc cde".parse::<Split>().is_err());
+        assert!("ab1 cd".parse::<Split>().is_err());
+        assert!("15; abc; 1.0".parse::<Split>().is_err());
+    }
+}